@@ -0,0 +1,220 @@
+use profig::error::ProfigError;
+use profig::types::FieldSchema;
+use profig::{config, edit, format, generator, json_schema, loader, paths, validator};
+use std::error::Error;
+
+const USAGE: &str = "Usage: profig <COMMAND> [ARGS]
+
+Commands:
+  validate --schema <SCHEMA> <CONFIG>...  Validate config files against a JSON Schema
+  convert <INPUT> <OUTPUT>                Convert a config file to another format
+  sample --schema <SCHEMA> <OUTPUT>       Generate a sample config file
+  docs --schema <SCHEMA> <OUTPUT>         Generate markdown documentation
+  fmt [--check] <CONFIG>...               Normalize the layout of TOML config files
+
+Schemas are JSON Schema files written by `MyConfig::export_schema(path)`.
+Formats are picked from file extensions (toml, json, jsonc, yaml, yml).
+";
+
+/// Positional arguments plus the options a command accepts.
+struct Args {
+    positional: Vec<String>,
+    schema: Option<String>,
+    check: bool,
+}
+
+fn parse_args(raw: &[String]) -> Result<Args, ProfigError> {
+    let mut args = Args {
+        positional: vec![],
+        schema: None,
+        check: false,
+    };
+    let mut iter = raw.iter();
+
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--schema" => {
+                args.schema = Some(iter.next().cloned().ok_or_else(|| {
+                    ProfigError::Custom("Missing value for '--schema'".to_string())
+                })?);
+            }
+            "--check" => args.check = true,
+            a if a.starts_with("--") => {
+                return Err(ProfigError::Custom(format!("Unknown option '{}'", a)));
+            }
+            _ => args.positional.push(arg.clone()),
+        }
+    }
+
+    Ok(args)
+}
+
+fn expect_positional(args: &Args, count: usize, what: &str) -> Result<(), ProfigError> {
+    if args.positional.len() != count {
+        return Err(ProfigError::Custom(format!(
+            "Expected {}\n\n{}",
+            what, USAGE
+        )));
+    }
+    Ok(())
+}
+
+fn load_schema(args: &Args) -> Result<(Vec<FieldSchema>, String), Box<dyn Error>> {
+    let path = args
+        .schema
+        .as_deref()
+        .ok_or_else(|| ProfigError::Custom("Missing '--schema <SCHEMA>'".to_string()))?;
+    let content = std::fs::read_to_string(path).map_err(ProfigError::from)?;
+    let doc: serde_json::Value =
+        serde_json::from_str(&content).map_err(|e| ProfigError::Parse {
+            format: "json",
+            error: e.to_string(),
+        })?;
+
+    let name = json_schema::title(&doc).unwrap_or("Config").to_string();
+    Ok((json_schema::import(&doc)?, name))
+}
+
+fn load_any(path: &str) -> Result<serde_json::Value, Box<dyn Error>> {
+    loader::load_as_value(path, &format::names())
+}
+
+fn validate(args: &Args) -> Result<bool, Box<dyn Error>> {
+    if args.positional.is_empty() {
+        return Err(Box::new(ProfigError::Custom(format!(
+            "Expected at least one config file\n\n{}",
+            USAGE
+        ))));
+    }
+    let (schema, _) = load_schema(args)?;
+
+    let mut all_ok = true;
+    for path in &args.positional {
+        let result = load_any(path).and_then(|mut val| {
+            let coerce = loader::string_values(path, &format::names());
+            let base_dir = paths::base_dir(path);
+            let warnings = config::prepare_value(&mut val, &schema, Some(&base_dir), coerce)?;
+            validator::check_required(&val, &schema)?;
+            Ok(warnings)
+        });
+
+        match result {
+            Ok(warnings) => {
+                for w in warnings {
+                    println!("{}: warning: {}", path, w);
+                }
+                println!("{}: OK", path);
+            }
+            Err(e) => {
+                println!("{}: {}", path, e);
+                all_ok = false;
+            }
+        }
+    }
+
+    Ok(all_ok)
+}
+
+fn convert(args: &Args) -> Result<bool, Box<dyn Error>> {
+    expect_positional(args, 2, "<INPUT> <OUTPUT>")?;
+    let (input, output) = (&args.positional[0], &args.positional[1]);
+
+    let val = load_any(input)?;
+    loader::save_sample(output, &val)?;
+    println!("Converted {} to {}.", input, output);
+
+    Ok(true)
+}
+
+fn sample(args: &Args) -> Result<bool, Box<dyn Error>> {
+    expect_positional(args, 1, "<OUTPUT>")?;
+    let (schema, _) = load_schema(args)?;
+
+    generator::sample_conf(&args.positional[0], &schema)?;
+
+    Ok(true)
+}
+
+fn docs(args: &Args) -> Result<bool, Box<dyn Error>> {
+    expect_positional(args, 1, "<OUTPUT>")?;
+    let (schema, name) = load_schema(args)?;
+
+    generator::generate_doc(&args.positional[0], &schema, &name)?;
+
+    Ok(true)
+}
+
+fn fmt(args: &Args) -> Result<bool, Box<dyn Error>> {
+    if args.positional.is_empty() {
+        return Err(Box::new(ProfigError::Custom(format!(
+            "Expected at least one config file\n\n{}",
+            USAGE
+        ))));
+    }
+
+    let mut all_ok = true;
+    for path in &args.positional {
+        // Only TOML can be rewritten without losing comments and key order
+        let ext = loader::extension_of(path);
+        if format::find_by_extension(&ext).is_none_or(|f| f.name() != "toml") {
+            return Err(Box::new(ProfigError::InvalidFormat(format!(
+                "'{}': fmt only supports TOML files",
+                path
+            ))));
+        }
+
+        let original = std::fs::read_to_string(path).map_err(ProfigError::from)?;
+        let formatted = edit::format_toml(&original)?;
+
+        if formatted == original {
+            continue;
+        }
+
+        if args.check {
+            println!("Would reformat {}", path);
+            all_ok = false;
+        } else {
+            std::fs::write(path, formatted).map_err(ProfigError::from)?;
+            println!("Formatted {}", path);
+        }
+    }
+
+    Ok(all_ok)
+}
+
+fn run(raw: &[String]) -> Result<bool, Box<dyn Error>> {
+    let Some((command, rest)) = raw.split_first() else {
+        print!("{}", USAGE);
+        return Ok(false);
+    };
+    let args = parse_args(rest)?;
+
+    match command.as_str() {
+        "validate" => validate(&args),
+        "convert" => convert(&args),
+        "sample" => sample(&args),
+        "docs" => docs(&args),
+        "fmt" => fmt(&args),
+        "-h" | "--help" | "help" => {
+            print!("{}", USAGE);
+            Ok(true)
+        }
+        other => Err(Box::new(ProfigError::Custom(format!(
+            "Unknown command '{}'\n\n{}",
+            other, USAGE
+        )))),
+    }
+}
+
+fn main() {
+    let raw: Vec<String> = std::env::args().skip(1).collect();
+
+    match run(&raw) {
+        Ok(true) => {}
+        Ok(false) => std::process::exit(1),
+        Err(e) => {
+            eprintln!("error: {}", e);
+            std::process::exit(2);
+        }
+    }
+}
@@ -8,7 +8,9 @@ pub enum ProfigError {
     Parse { format: &'static str, error: String },
     InvalidFormat(String),
     Validation(String),
+    Include { chain: Vec<String>, error: String },
     Custom(String),
+    Help(String),
 }
 
 impl fmt::Display for ProfigError {
@@ -20,7 +22,11 @@ impl fmt::Display for ProfigError {
             }
             ProfigError::InvalidFormat(msg) => write!(f, "Invalid Format: {}", msg),
             ProfigError::Validation(msg) => write!(f, "Validation Error: {}", msg),
+            ProfigError::Include { chain, error } => {
+                write!(f, "Include Error ({}): {}", chain.join(" -> "), error)
+            }
             ProfigError::Custom(msg) => write!(f, "{}", msg),
+            ProfigError::Help(text) => write!(f, "{}", text),
         }
     }
 }
@@ -0,0 +1,39 @@
+/// Built-in config formats, with the file extensions that map to them.
+///
+/// This is the single place where their extensions are declared: the derive checks
+/// `#[profig(format = "...")]` against it at compile time, and the runtime registry in
+/// `profig::format` takes the built-in formats' extensions from it.
+pub const FORMATS: &[(&str, &[&str])] = &[
+    ("toml", &["toml"]),
+    ("json", &["json", "jsonc"]),
+    ("yaml", &["yaml", "yml"]),
+];
+
+/// Returns the format name registered for a file extension (case-insensitive).
+pub fn format_for_extension(ext: &str) -> Option<&'static str> {
+    let ext = ext.to_ascii_lowercase();
+    FORMATS
+        .iter()
+        .find(|(_, exts)| exts.contains(&ext.as_str()))
+        .map(|(name, _)| *name)
+}
+
+/// Extensions of the built-in format `name`, or an empty list for unknown names.
+pub fn extensions(name: &str) -> &'static [&'static str] {
+    FORMATS
+        .iter()
+        .find(|(fmt, _)| *fmt == name)
+        .map(|(_, exts)| *exts)
+        .unwrap_or(&[])
+}
+
+/// Resolves a user-supplied format name, accepting either the canonical name
+/// (`"yaml"`) or any of its extensions (`"yml"`).
+pub fn resolve_format(name: &str) -> Option<&'static str> {
+    let name = name.trim().to_ascii_lowercase();
+    FORMATS
+        .iter()
+        .find(|(fmt, _)| *fmt == name)
+        .map(|(fmt, _)| *fmt)
+        .or_else(|| format_for_extension(&name))
+}
@@ -1,2 +1,4 @@
 pub mod error;
+pub mod formats;
 pub mod types;
+pub mod units;
@@ -1,9 +1,101 @@
-#[derive(Debug)]
+/// Names accepted by `#[profig(format = "...")]` on string fields.
+pub const STRING_FORMATS: &[&str] = &[
+    "url",
+    "email",
+    "ip",
+    "ipv4",
+    "ipv6",
+    "hostname",
+    "socket_addr",
+    "uuid",
+];
+
+/// Width and signedness of an integer field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IntKind {
+    I8,
+    I16,
+    I32,
+    I64,
+    Isize,
+    U8,
+    U16,
+    U32,
+    U64,
+    Usize,
+}
+
+impl IntKind {
+    pub const ALL: [IntKind; 10] = [
+        IntKind::I8,
+        IntKind::I16,
+        IntKind::I32,
+        IntKind::I64,
+        IntKind::Isize,
+        IntKind::U8,
+        IntKind::U16,
+        IntKind::U32,
+        IntKind::U64,
+        IntKind::Usize,
+    ];
+
+    /// Rust name of the type, e.g. `u16`.
+    pub fn name(self) -> &'static str {
+        match self {
+            IntKind::I8 => "i8",
+            IntKind::I16 => "i16",
+            IntKind::I32 => "i32",
+            IntKind::I64 => "i64",
+            IntKind::Isize => "isize",
+            IntKind::U8 => "u8",
+            IntKind::U16 => "u16",
+            IntKind::U32 => "u32",
+            IntKind::U64 => "u64",
+            IntKind::Usize => "usize",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<IntKind> {
+        IntKind::ALL.into_iter().find(|k| k.name() == name)
+    }
+
+    /// Smallest and largest value the type can hold.
+    pub fn range(self) -> (i128, i128) {
+        match self {
+            IntKind::I8 => (i8::MIN as i128, i8::MAX as i128),
+            IntKind::I16 => (i16::MIN as i128, i16::MAX as i128),
+            IntKind::I32 => (i32::MIN as i128, i32::MAX as i128),
+            IntKind::I64 => (i64::MIN as i128, i64::MAX as i128),
+            IntKind::Isize => (isize::MIN as i128, isize::MAX as i128),
+            IntKind::U8 => (0, u8::MAX as i128),
+            IntKind::U16 => (0, u16::MAX as i128),
+            IntKind::U32 => (0, u32::MAX as i128),
+            IntKind::U64 => (0, u64::MAX as i128),
+            IntKind::Usize => (0, usize::MAX as i128),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
 pub enum FieldType {
-    Int,
+    Int(IntKind),
     Float,
     Str,
     Bool,
+    /// `std::time::Duration`, written as `30s`, `1h30m`, ...
+    Duration,
+    /// `profig::units::ByteSize`, written as `512MiB`, `1GB`, ...
+    Bytes,
+    /// `std::path::PathBuf`, resolved against the config file's directory
+    Path,
+}
+
+/// Condition of a `require_if` rule: `field` equals `equals`, or, without a
+/// value, `field` is set to anything but `null`/`false`.
+#[derive(Debug, Clone)]
+pub struct Condition {
+    pub field: String,
+    pub equals: Option<serde_json::Value>,
 }
 
 #[derive(Debug, Default)]
@@ -13,11 +105,33 @@ pub struct MetaField {
     pub max: Option<f64>,
     pub regex: Option<String>,
     pub doc: Option<String>,
+    pub secret: bool,
+    pub deprecated: Option<String>,
+    pub aliases: Vec<String>,
+    pub required_if: Vec<Condition>,
+    pub conflicts_with: Vec<String>,
+    pub gt: Option<String>,
+    pub lt: Option<String>,
+    pub min_len: Option<usize>,
+    pub max_len: Option<usize>,
+    pub non_empty: bool,
+    pub trim: bool,
+    pub lowercase: bool,
+    pub uppercase: bool,
+    pub ascii_only: bool,
+    pub format: Option<String>,
+    pub must_exist: bool,
+    pub is_file: bool,
+    pub is_dir: bool,
+    pub readable: bool,
+    pub writable: bool,
+    pub create_dir: bool,
 }
 
 #[derive(Debug)]
 pub struct FieldSchema {
     pub name: String,
     pub ty: FieldType,
+    pub optional: bool,
     pub metadata: MetaField,
 }
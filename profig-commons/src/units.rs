@@ -0,0 +1,272 @@
+const DURATION_UNITS: &[(&str, f64)] = &[
+    ("ns", 1e-9),
+    ("us", 1e-6),
+    ("µs", 1e-6),
+    ("ms", 1e-3),
+    ("s", 1.0),
+    ("m", 60.0),
+    ("h", 3600.0),
+    ("d", 86400.0),
+];
+
+const BYTE_UNITS: &[(&str, u64)] = &[
+    ("b", 1),
+    ("kb", 1_000),
+    ("mb", 1_000_000),
+    ("gb", 1_000_000_000),
+    ("tb", 1_000_000_000_000),
+    ("pb", 1_000_000_000_000_000),
+    ("kib", 1 << 10),
+    ("mib", 1 << 20),
+    ("gib", 1 << 30),
+    ("tib", 1 << 40),
+    ("pib", 1 << 50),
+];
+
+/// Splits `s` into `(number, unit)` pairs, e.g. `1h30m` into `[(1, "h"), (30, "m")]`.
+fn split_parts(s: &str) -> Result<Vec<(f64, &str)>, String> {
+    let mut parts = vec![];
+    let mut rest = s.trim();
+    if rest.is_empty() {
+        return Err("empty value".to_string());
+    }
+
+    while !rest.is_empty() {
+        let num_end = rest
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(rest.len());
+        if num_end == 0 {
+            return Err(format!("expected a number at '{}'", rest));
+        }
+        let number: f64 = rest[..num_end]
+            .parse()
+            .map_err(|_| format!("invalid number '{}'", &rest[..num_end]))?;
+
+        let after = rest[num_end..].trim_start();
+        let unit_end = after
+            .find(|c: char| c.is_ascii_digit() || c.is_whitespace())
+            .unwrap_or(after.len());
+        parts.push((number, &after[..unit_end]));
+        rest = after[unit_end..].trim_start();
+    }
+
+    Ok(parts)
+}
+
+/// Parses a duration such as `30s`, `5m`, `1h30m` or `1.5h` into seconds.
+///
+/// A bare number is taken as seconds.
+pub fn parse_duration(s: &str) -> Result<f64, String> {
+    let parts = split_parts(s)?;
+    if let [(n, "")] = parts.as_slice() {
+        return Ok(*n);
+    }
+
+    let mut secs = 0.0;
+    for (n, unit) in parts {
+        let factor = DURATION_UNITS
+            .iter()
+            .find(|(u, _)| *u == unit)
+            .map(|(_, f)| *f)
+            .ok_or_else(|| {
+                if unit.is_empty() {
+                    "missing unit (ns, us, ms, s, m, h, d)".to_string()
+                } else {
+                    format!("unknown unit '{}' (expected ns, us, ms, s, m, h, d)", unit)
+                }
+            })?;
+        secs += n * factor;
+    }
+
+    Ok(secs)
+}
+
+/// Parses a byte size such as `512MiB`, `1GB` or `1.5 kb` into bytes.
+///
+/// Units are case-insensitive; `KB`/`MB`/... are powers of 1000 and `KiB`/`MiB`/...
+/// powers of 1024. A bare number is taken as bytes.
+pub fn parse_bytes(s: &str) -> Result<u64, String> {
+    let parts = split_parts(s)?;
+    let [(n, unit)] = parts.as_slice() else {
+        return Err("expected a single number and unit".to_string());
+    };
+
+    let unit = unit.to_ascii_lowercase();
+    let factor = if unit.is_empty() {
+        1
+    } else {
+        BYTE_UNITS
+            .iter()
+            .find(|(u, _)| *u == unit)
+            .map(|(_, f)| *f)
+            .ok_or_else(|| {
+                format!(
+                    "unknown unit '{}' (expected B, KB, MB, GB, TB, PB, KiB, MiB, GiB, TiB, PiB)",
+                    unit
+                )
+            })?
+    };
+
+    let bytes = n * factor as f64;
+    if bytes.fract() != 0.0 {
+        return Err("not a whole number of bytes".to_string());
+    }
+    if bytes >= u64::MAX as f64 {
+        return Err("too large".to_string());
+    }
+
+    Ok(bytes as u64)
+}
+
+/// Formats seconds as a duration, e.g. `5400.0` as `1h30m` and `0.25` as `250ms`.
+pub fn format_duration(secs: f64) -> String {
+    if secs <= 0.0 {
+        return "0s".to_string();
+    }
+
+    let mut nanos = (secs * 1e9).round() as u128;
+    let mut out = String::new();
+    for (unit, factor) in [
+        ("d", 86_400_000_000_000u128),
+        ("h", 3_600_000_000_000),
+        ("m", 60_000_000_000),
+        ("s", 1_000_000_000),
+        ("ms", 1_000_000),
+        ("us", 1_000),
+        ("ns", 1),
+    ] {
+        if nanos >= factor {
+            out.push_str(&format!("{}{}", nanos / factor, unit));
+            nanos %= factor;
+        }
+    }
+
+    out
+}
+
+/// Formats bytes with the largest unit that divides them exactly, e.g. `512MiB` or `1GB`.
+pub fn format_bytes(bytes: u64) -> String {
+    if bytes == 0 {
+        return "0B".to_string();
+    }
+
+    let units = [
+        ("PiB", 1u64 << 50),
+        ("PB", 1_000_000_000_000_000),
+        ("TiB", 1 << 40),
+        ("TB", 1_000_000_000_000),
+        ("GiB", 1 << 30),
+        ("GB", 1_000_000_000),
+        ("MiB", 1 << 20),
+        ("MB", 1_000_000),
+        ("KiB", 1 << 10),
+        ("KB", 1_000),
+    ];
+
+    units
+        .iter()
+        .find(|(_, f)| bytes.is_multiple_of(*f))
+        .map(|(unit, f)| format!("{}{}", bytes / f, unit))
+        .unwrap_or_else(|| format!("{}B", bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_durations() {
+        assert_eq!(parse_duration("30s"), Ok(30.0));
+        assert_eq!(parse_duration("5m"), Ok(300.0));
+        assert_eq!(parse_duration("1h30m"), Ok(5400.0));
+        assert_eq!(parse_duration("1h 30m"), Ok(5400.0));
+        assert_eq!(parse_duration("1.5h"), Ok(5400.0));
+        assert_eq!(parse_duration("250ms"), Ok(0.25));
+        assert_eq!(parse_duration("1d"), Ok(86400.0));
+    }
+
+    #[test]
+    fn bare_duration_is_seconds() {
+        assert_eq!(parse_duration("90"), Ok(90.0));
+        assert_eq!(parse_duration(" 2.5 "), Ok(2.5));
+    }
+
+    #[test]
+    fn rejects_bad_durations() {
+        assert_eq!(parse_duration(""), Err("empty value".to_string()));
+        assert_eq!(
+            parse_duration("5x"),
+            Err("unknown unit 'x' (expected ns, us, ms, s, m, h, d)".to_string())
+        );
+        // Only a lone number may leave out the unit
+        assert_eq!(
+            parse_duration("1h30"),
+            Err("missing unit (ns, us, ms, s, m, h, d)".to_string())
+        );
+        assert!(parse_duration("h").is_err());
+        assert!(parse_duration("1.2.3s").is_err());
+        assert!(parse_duration("-5s").is_err());
+    }
+
+    #[test]
+    fn parses_byte_sizes() {
+        assert_eq!(parse_bytes("512MiB"), Ok(512 << 20));
+        assert_eq!(parse_bytes("1GB"), Ok(1_000_000_000));
+        assert_eq!(parse_bytes("1.5 kb"), Ok(1500));
+        assert_eq!(parse_bytes("2kib"), Ok(2048));
+        assert_eq!(parse_bytes("7B"), Ok(7));
+    }
+
+    #[test]
+    fn bare_byte_size_is_bytes() {
+        assert_eq!(parse_bytes("4096"), Ok(4096));
+    }
+
+    #[test]
+    fn rejects_bad_byte_sizes() {
+        assert_eq!(parse_bytes(""), Err("empty value".to_string()));
+        assert!(parse_bytes("5xb")
+            .unwrap_err()
+            .starts_with("unknown unit 'xb'"));
+        assert_eq!(
+            parse_bytes("1kb 2mb"),
+            Err("expected a single number and unit".to_string())
+        );
+        assert_eq!(
+            parse_bytes("1.5b"),
+            Err("not a whole number of bytes".to_string())
+        );
+        assert_eq!(parse_bytes("16384PiB"), Err("too large".to_string()));
+    }
+
+    #[test]
+    fn formats_durations() {
+        assert_eq!(format_duration(0.0), "0s");
+        assert_eq!(format_duration(-3.0), "0s");
+        assert_eq!(format_duration(30.0), "30s");
+        assert_eq!(format_duration(5400.0), "1h30m");
+        assert_eq!(format_duration(0.25), "250ms");
+        assert_eq!(format_duration(90061.0), "1d1h1m1s");
+        assert_eq!(format_duration(1e-9), "1ns");
+    }
+
+    #[test]
+    fn formats_byte_sizes() {
+        assert_eq!(format_bytes(0), "0B");
+        assert_eq!(format_bytes(512 << 20), "512MiB");
+        assert_eq!(format_bytes(1_000_000_000), "1GB");
+        assert_eq!(format_bytes(2048), "2KiB");
+        assert_eq!(format_bytes(1500), "1500B");
+        assert_eq!(format_bytes(u64::MAX), format!("{}B", u64::MAX));
+    }
+
+    #[test]
+    fn formatted_values_parse_back() {
+        for secs in [1.0, 0.25, 5400.0, 90061.5, 1e-6] {
+            assert_eq!(parse_duration(&format_duration(secs)), Ok(secs));
+        }
+        for bytes in [1, 1500, 2048, 512 << 20, 1_000_000_000] {
+            assert_eq!(parse_bytes(&format_bytes(bytes)), Ok(bytes));
+        }
+    }
+}
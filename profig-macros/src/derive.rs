@@ -1,11 +1,346 @@
-use profig_commons::types::{FieldSchema, FieldType, MetaField};
-use quote::{quote};
+use profig_commons::formats::{resolve_format, FORMATS};
+use profig_commons::types::{
+    Condition, FieldSchema, FieldType, IntKind, MetaField, STRING_FORMATS,
+};
+use profig_commons::units;
+use quote::{format_ident, quote};
+use std::collections::HashMap;
+use syn::punctuated::Punctuated;
 use syn::{Data, DeriveInput, Fields, Lit};
 
+/// Schema type of a supported field type, plus the string format it implies
+/// (`IpAddr` is a string that must parse as an IP address).
+fn scalar_type(ident: &str) -> Option<(FieldType, Option<&'static str>)> {
+    Some(match ident {
+        "String" => (FieldType::Str, None),
+        "bool" => (FieldType::Bool, None),
+        int if IntKind::from_name(int).is_some() => {
+            (FieldType::Int(IntKind::from_name(int)?), None)
+        }
+        "f32" | "f64" => (FieldType::Float, None),
+        "Duration" => (FieldType::Duration, None),
+        "ByteSize" => (FieldType::Bytes, None),
+        "PathBuf" => (FieldType::Path, None),
+        "IpAddr" => (FieldType::Str, Some("ip")),
+        "Ipv4Addr" => (FieldType::Str, Some("ipv4")),
+        "Ipv6Addr" => (FieldType::Str, Some("ipv6")),
+        "SocketAddr" => (FieldType::Str, Some("socket_addr")),
+        "Url" => (FieldType::Str, Some("url")),
+        _ => return None,
+    })
+}
+
+/// Schema type of a field, whether it is an `Option`, and the format its type implies.
+fn field_type_of(ty: &syn::Type) -> syn::Result<(FieldType, bool, Option<&'static str>)> {
+    let unsupported = |ty: &syn::Type| {
+        syn::Error::new_spanned(
+            ty,
+            "Unsupported field type; expected an integer, f32/f64, bool, String, Duration, \
+             ByteSize, PathBuf, an IP or socket address, or Url, optionally in Option<...>",
+        )
+    };
+    let last_segment = |ty: &syn::Type| match ty {
+        syn::Type::Path(type_path) => type_path.path.segments.last().cloned(),
+        _ => None,
+    };
+
+    let segment = last_segment(ty).ok_or_else(|| unsupported(ty))?;
+    if segment.ident != "Option" {
+        let (field_type, format) =
+            scalar_type(&segment.ident.to_string()).ok_or_else(|| unsupported(ty))?;
+        return Ok((field_type, false, format));
+    }
+
+    let inner = match &segment.arguments {
+        syn::PathArguments::AngleBracketed(args) => match args.args.first() {
+            Some(syn::GenericArgument::Type(inner)) => inner,
+            _ => return Err(unsupported(ty)),
+        },
+        _ => return Err(unsupported(ty)),
+    };
+    let (field_type, format) = last_segment(inner)
+        .and_then(|s| scalar_type(&s.ident.to_string()))
+        .ok_or_else(|| unsupported(inner))?;
+    Ok((field_type, true, format))
+}
+
+/// Whether `gt`/`lt` can compare fields of these types.
+fn comparable(a: &FieldType, b: &FieldType) -> bool {
+    matches!(
+        (a, b),
+        (
+            FieldType::Int(_) | FieldType::Float,
+            FieldType::Int(_) | FieldType::Float
+        ) | (FieldType::Duration, FieldType::Duration)
+            | (FieldType::Bytes, FieldType::Bytes)
+    )
+}
+
+/// Parses a duration (in seconds) or byte size written as a string, e.g. `min = "1s"`.
+fn parse_unit(s: &syn::LitStr, ty: &FieldType) -> syn::Result<f64> {
+    let parsed = match ty {
+        FieldType::Duration => units::parse_duration(&s.value()),
+        FieldType::Bytes => units::parse_bytes(&s.value()).map(|b| b as f64),
+        _ => {
+            return Err(syn::Error::new_spanned(
+                s,
+                "String bounds are only supported on Duration and ByteSize fields",
+            ))
+        }
+    };
+    parsed.map_err(|e| syn::Error::new_spanned(s, format!("Invalid value '{}': {}", s.value(), e)))
+}
+
+/// JSON value of a literal used in a `require_if` condition.
+fn lit_to_value(lit: &Lit) -> syn::Result<serde_json::Value> {
+    Ok(match lit {
+        Lit::Str(s) => serde_json::Value::from(s.value()),
+        Lit::Bool(b) => serde_json::Value::from(b.value),
+        Lit::Int(i) => serde_json::Value::from(i.base10_parse::<i64>()?),
+        Lit::Float(f) => serde_json::Value::from(f.base10_parse::<f64>()?),
+        other => {
+            return Err(syn::Error::new_spanned(
+                other,
+                "Expected a string, bool or number literal",
+            ))
+        }
+    })
+}
+
+fn value_tokens(value: &serde_json::Value) -> proc_macro2::TokenStream {
+    match value {
+        serde_json::Value::Bool(b) => quote!(::serde_json::Value::from(#b)),
+        serde_json::Value::Number(n) => match n.as_i64() {
+            Some(i) => quote!(::serde_json::Value::from(#i)),
+            None => {
+                let f = n.as_f64().unwrap_or_default();
+                quote!(::serde_json::Value::from(#f))
+            }
+        },
+        serde_json::Value::String(s) => quote!(::serde_json::Value::from(#s)),
+        _ => quote!(::serde_json::Value::Null),
+    }
+}
+
+/// Checks a `default` against the field's type and constraints, so mistakes like
+/// `default = "four"` fail to compile instead of failing at load time.
+fn check_default(text: &str, meta: &MetaField, ty: &FieldType) -> Result<(), String> {
+    let amount = match ty {
+        FieldType::Int(kind) => {
+            let v: i128 = text
+                .parse()
+                .map_err(|_| format!("Default '{}' is not an integer", text))?;
+            let (lo, hi) = kind.range();
+            if v < lo || v > hi {
+                return Err(format!(
+                    "Default {} is outside the range of {} ({}..={})",
+                    v,
+                    kind.name(),
+                    lo,
+                    hi
+                ));
+            }
+            Some(v as f64)
+        }
+        FieldType::Float => Some(
+            text.parse::<f64>()
+                .map_err(|_| format!("Default '{}' is not a number", text))?,
+        ),
+        FieldType::Bool => {
+            text.parse::<bool>()
+                .map_err(|_| format!("Default '{}' is not true or false", text))?;
+            None
+        }
+        FieldType::Duration => Some(
+            units::parse_duration(text)
+                .map_err(|e| format!("Invalid default '{}': {}", text, e))?,
+        ),
+        FieldType::Bytes => Some(
+            units::parse_bytes(text).map_err(|e| format!("Invalid default '{}': {}", text, e))?
+                as f64,
+        ),
+        FieldType::Str | FieldType::Path => None,
+    };
+
+    let bound = |b: f64| match ty {
+        FieldType::Duration => units::format_duration(b),
+        FieldType::Bytes => units::format_bytes(b as u64),
+        _ => b.to_string(),
+    };
+    if let Some(n) = amount {
+        if let Some(min) = meta.min.filter(|min| n < *min) {
+            return Err(format!(
+                "Default '{}' is less than min {}",
+                text,
+                bound(min)
+            ));
+        }
+        if let Some(max) = meta.max.filter(|max| n > *max) {
+            return Err(format!(
+                "Default '{}' is greater than max {}",
+                text,
+                bound(max)
+            ));
+        }
+    }
+
+    if matches!(ty, FieldType::Str) {
+        let s = if meta.trim { text.trim() } else { text };
+        let len = s.chars().count();
+        if meta.non_empty && len == 0 {
+            return Err("Default is empty, but the field is non_empty".to_string());
+        }
+        if let Some(min_len) = meta.min_len.filter(|n| len < *n) {
+            return Err(format!(
+                "Default '{}' is shorter than {} characters",
+                text, min_len
+            ));
+        }
+        if let Some(max_len) = meta.max_len.filter(|n| len > *n) {
+            return Err(format!(
+                "Default '{}' is longer than {} characters",
+                text, max_len
+            ));
+        }
+        if meta.ascii_only && !s.is_ascii() {
+            return Err(format!("Default '{}' is not ASCII", text));
+        }
+        if let Some(rx) = &meta.regex {
+            let re = regex::Regex::new(rx).map_err(|e| format!("Invalid regex: {}", e))?;
+            if !re.is_match(text) {
+                return Err(format!("Default '{}' does not match regex '{}'", text, rx));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Parses `(a, b = value, ...)`, the argument list of a cross-field rule.
+fn parse_rule_args(
+    meta: &syn::meta::ParseNestedMeta,
+) -> syn::Result<Vec<(syn::Ident, Option<Lit>)>> {
+    let mut args = vec![];
+    meta.parse_nested_meta(|inner| {
+        let ident = inner.path.require_ident()?.clone();
+        let value = if inner.input.peek(syn::Token![=]) {
+            Some(inner.value()?.parse()?)
+        } else {
+            None
+        };
+        args.push((ident, value));
+        Ok(())
+    })?;
+    Ok(args)
+}
+
+/// The name given to `key` in `#[serde(...)]`: `rename = "x"`, or
+/// `rename(serialize = "x", deserialize = "x")` with the same name for both.
+///
+/// Different names for reading and writing are rejected, since `save` would write
+/// keys that `load` doesn't accept.
+fn serde_name(attrs: &[syn::Attribute], key: &str) -> syn::Result<Option<syn::LitStr>> {
+    let as_str = |expr: &syn::Expr| match expr {
+        syn::Expr::Lit(syn::ExprLit {
+            lit: Lit::Str(s), ..
+        }) => Some(s.clone()),
+        _ => None,
+    };
+
+    let found = attrs
+        .iter()
+        .filter(|a| a.path().is_ident("serde"))
+        // Malformed serde attributes are left for serde to report
+        .filter_map(|a| {
+            a.parse_args_with(Punctuated::<syn::Meta, syn::Token![,]>::parse_terminated)
+                .ok()
+        })
+        .flatten()
+        .find(|m| m.path().is_ident(key));
+
+    match found {
+        Some(syn::Meta::NameValue(nv)) => Ok(as_str(&nv.value)),
+        Some(syn::Meta::List(list)) => {
+            let Ok(parts) = list.parse_args_with(
+                Punctuated::<syn::MetaNameValue, syn::Token![,]>::parse_terminated,
+            ) else {
+                return Ok(None);
+            };
+            let part = |name: &str| {
+                parts
+                    .iter()
+                    .find(|nv| nv.path.is_ident(name))
+                    .and_then(|nv| as_str(&nv.value))
+            };
+            let (ser, de) = (part("serialize"), part("deserialize"));
+            if ser.as_ref().map(syn::LitStr::value) != de.as_ref().map(syn::LitStr::value) {
+                return Err(syn::Error::new_spanned(
+                    list,
+                    format!(
+                        "'{}' needs the same name for serialize and deserialize, as `save` writes the keys `load` reads",
+                        key
+                    ),
+                ));
+            }
+            Ok(de)
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Applies a serde `rename_all` rule to a snake_case field name, as serde does.
+fn rename_field(name: &str, rule: &str) -> Option<String> {
+    let pascal = || {
+        name.split('_')
+            .map(|word| {
+                let mut chars = word.chars();
+                chars
+                    .next()
+                    .map(|c| c.to_ascii_uppercase().to_string() + chars.as_str())
+                    .unwrap_or_default()
+            })
+            .collect::<String>()
+    };
+
+    Some(match rule {
+        "lowercase" | "snake_case" => name.to_string(),
+        "UPPERCASE" | "SCREAMING_SNAKE_CASE" => name.to_ascii_uppercase(),
+        "PascalCase" => pascal(),
+        "camelCase" => {
+            let pascal = pascal();
+            let mut chars = pascal.chars();
+            chars
+                .next()
+                .map(|c| c.to_ascii_lowercase().to_string() + chars.as_str())
+                .unwrap_or_default()
+        }
+        "kebab-case" => name.replace('_', "-"),
+        "SCREAMING-KEBAB-CASE" => name.to_ascii_uppercase().replace('_', "-"),
+        _ => return None,
+    })
+}
+
 pub fn expand_derive_profig(input: DeriveInput) -> proc_macro2::TokenStream {
     let name = input.ident.clone();
 
     let mut formats = vec![];
+    let mut custom_formats = vec![];
+    let mut profiles = false;
+    let mut profile_env = "APP_PROFILE".to_string();
+    let mut redacted_debug = false;
+    let mut coerce = false;
+    let mut version: Option<u64> = None;
+    let mut migrations: Option<syn::ExprPath> = None;
+    let mut struct_validators: Vec<syn::ExprPath> = vec![];
+    let mut field_validators: Vec<(syn::Ident, syn::ExprPath, bool)> = vec![];
+    // Cross-field rules, attached to their fields once all fields are parsed
+    let mut require_if_rules: Vec<(syn::Ident, syn::Ident, Option<Lit>)> = vec![];
+    let mut exclusive_groups: Vec<Vec<syn::Ident>> = vec![];
+    let mut field_refs: Vec<syn::LitStr> = vec![];
+    // `gt`/`lt` targets with the type of the field comparing against them
+    let mut comparisons: Vec<(syn::LitStr, FieldType)> = vec![];
+    // `default_fn` paths by config key, called whenever the schema is built
+    let mut default_fns: HashMap<String, syn::ExprPath> = HashMap::new();
     let mut schema = vec![];
 
     for attr in &input.attrs {
@@ -31,6 +366,83 @@ pub fn expand_derive_profig(input: DeriveInput) -> proc_macro2::TokenStream {
                             "Expected string literal for format",
                         ));
                     }
+                } else if key == "custom_format" {
+                    let value: Lit = meta.value()?.parse()?;
+                    if let Lit::Str(litstr) = value {
+                        custom_formats = litstr
+                            .value()
+                            .split(',')
+                            .map(|s| s.trim().to_ascii_lowercase())
+                            .filter(|s| !s.is_empty())
+                            .collect();
+                    } else {
+                        return Err(syn::Error::new_spanned(
+                            value,
+                            "Expected string literal for custom_format",
+                        ));
+                    }
+                } else if key == "profiles" {
+                    profiles = true;
+                } else if key == "debug" {
+                    redacted_debug = true;
+                } else if key == "coerce" {
+                    coerce = true;
+                } else if key == "version" {
+                    let value: Lit = meta.value()?.parse()?;
+                    if let Lit::Int(i) = value {
+                        version = Some(i.base10_parse()?);
+                    } else {
+                        return Err(syn::Error::new_spanned(
+                            value,
+                            "Expected integer literal for version",
+                        ));
+                    }
+                } else if key == "migrations" {
+                    let value: Lit = meta.value()?.parse()?;
+                    if let Lit::Str(litstr) = value {
+                        migrations = Some(litstr.parse()?);
+                    } else {
+                        return Err(syn::Error::new_spanned(
+                            value,
+                            "Expected string literal for migrations",
+                        ));
+                    }
+                } else if key == "require_if" {
+                    let mut args = parse_rule_args(&meta)?.into_iter();
+                    match (args.next(), args.next(), args.next()) {
+                        (Some((field, None)), Some((cond, value)), None) => {
+                            require_if_rules.push((field, cond, value))
+                        }
+                        _ => return Err(meta.error(
+                            "Expected require_if(field, other) or require_if(field, other = value)",
+                        )),
+                    }
+                } else if key == "mutually_exclusive" {
+                    let args = parse_rule_args(&meta)?;
+                    if args.len() < 2 || args.iter().any(|(_, v)| v.is_some()) {
+                        return Err(meta.error("Expected mutually_exclusive(field, field, ...)"));
+                    }
+                    exclusive_groups.push(args.into_iter().map(|(f, _)| f).collect());
+                } else if key == "validate" {
+                    let value: Lit = meta.value()?.parse()?;
+                    if let Lit::Str(litstr) = value {
+                        struct_validators.push(litstr.parse()?);
+                    } else {
+                        return Err(syn::Error::new_spanned(
+                            value,
+                            "Expected string literal for validate",
+                        ));
+                    }
+                } else if key == "profile_env" {
+                    let value: Lit = meta.value()?.parse()?;
+                    if let Lit::Str(litstr) = value {
+                        profile_env = litstr.value();
+                    } else {
+                        return Err(syn::Error::new_spanned(
+                            value,
+                            "Expected string literal for profile_env",
+                        ));
+                    }
                 } else {
                     return Err(syn::Error::new_spanned(
                         meta.path,
@@ -47,67 +459,58 @@ pub fn expand_derive_profig(input: DeriveInput) -> proc_macro2::TokenStream {
         }
     }
 
+    // Config keys follow serde's `rename` and `rename_all`, so profig and serde agree
+    let rename_all = match serde_name(&input.attrs, "rename_all") {
+        Ok(rule) => rule,
+        Err(e) => return e.to_compile_error(),
+    };
+    let mut keys: HashMap<String, String> = HashMap::new();
+    if let Data::Struct(data_struct) = &input.data {
+        for field in &data_struct.fields {
+            let Some(ident) = &field.ident else { continue };
+            let plain = syn::ext::IdentExt::unraw(ident).to_string();
+            let rename = match serde_name(&field.attrs, "rename") {
+                Ok(rename) => rename,
+                Err(e) => return e.to_compile_error(),
+            };
+            let key = match (rename, &rename_all) {
+                (Some(rename), _) => rename.value(),
+                (None, Some(rule)) => match rename_field(&plain, &rule.value()) {
+                    Some(key) => key,
+                    None => {
+                        return syn::Error::new_spanned(
+                            rule,
+                            format!("Unknown rename rule '{}'", rule.value()),
+                        )
+                        .to_compile_error()
+                    }
+                },
+                (None, None) => plain,
+            };
+            keys.insert(ident.to_string(), key);
+        }
+    }
+    let key_of = |ident: &str| {
+        keys.get(ident)
+            .cloned()
+            .unwrap_or_else(|| ident.to_string())
+    };
+
     // Make sure it's a struct
     if let Data::Struct(data_struct) = input.data.clone() {
         if let Fields::Named(fields_named) = data_struct.fields {
             for field in fields_named.named.iter() {
-                let field_name = field.ident.as_ref().unwrap().to_string();
+                let field_name = key_of(&field.ident.as_ref().unwrap().to_string());
                 for attr in &field.attrs {
                     if attr.path().is_ident("profig") {
                         let mut meta_field = MetaField::default();
+                        let mut default_lit: Option<Lit> = None;
+                        let mut default_fn: Option<syn::ExprPath> = None;
 
-                        let field_type = match &field.ty {
-                            syn::Type::Path(type_path) => {
-                                let last_segment = type_path.path.segments.last().unwrap();
-                                let ident =
-                                    type_path.path.segments.last().unwrap().ident.to_string();
-
-                                if ident == "Option" {
-                                    // Option<T> Handling
-                                    if let syn::PathArguments::AngleBracketed(inner_args) =
-                                        &last_segment.arguments
-                                    {
-                                        if let Some(syn::GenericArgument::Type(syn::Type::Path(
-                                            inner_ty_path,
-                                        ))) = inner_args.args.first()
-                                        {
-                                            let inner_ident = inner_ty_path
-                                                .path
-                                                .segments
-                                                .last()
-                                                .unwrap()
-                                                .ident
-                                                .to_string();
-
-                                            match inner_ident.as_str() {
-                                                "String" => FieldType::Str,
-                                                "bool" => FieldType::Bool,
-                                                "i8" | "i16" | "i32" | "i64" | "isize" | "u8"
-                                                | "u16" | "u32" | "u64" | "usize" => FieldType::Int,
-                                                "f32" | "f64" => FieldType::Float,
-                                                _ => panic!(
-                                                    "Unsupported type inside Option<>: {}",
-                                                    inner_ident
-                                                ),
-                                            }
-                                        } else {
-                                            panic!("Option<> must have a concrete type argument");
-                                        }
-                                    } else {
-                                        panic!("Unsupported Option<> type structure");
-                                    }
-                                } else {
-                                    match ident.as_str() {
-                                        "String" => FieldType::Str,
-                                        "bool" => FieldType::Bool,
-                                        "i8" | "i16" | "i32" | "i64" | "isize" | "u8" | "u16"
-                                        | "u32" | "u64" | "usize" => FieldType::Int,
-                                        "f32" | "f64" => FieldType::Float,
-                                        _ => panic!("Unsupported type: {}", ident),
-                                    }
-                                }
-                            }
-                            _ => panic!("Unsupported type structure for field: {}", &field_name),
+                        let (field_type, optional, implied_format) = match field_type_of(&field.ty)
+                        {
+                            Ok(t) => t,
+                            Err(e) => return e.to_compile_error(),
                         };
 
                         // Parse #[profig(...)] using syn 2.0
@@ -118,13 +521,85 @@ pub fn expand_derive_profig(input: DeriveInput) -> proc_macro2::TokenStream {
                                 .map(|i| i.to_string())
                                 .unwrap_or_default();
 
+                            let numeric_only = ["min", "max", "gt", "lt"];
+                            if numeric_only.contains(&key.as_str())
+                                && matches!(
+                                    field_type,
+                                    FieldType::Str | FieldType::Bool | FieldType::Path
+                                )
+                            {
+                                return Err(meta.error(format!(
+                                    "'{}' only applies to numeric, Duration and ByteSize fields",
+                                    key
+                                )));
+                            }
+
+                            let string_only = [
+                                "regex",
+                                "min_len",
+                                "max_len",
+                                "non_empty",
+                                "trim",
+                                "lowercase",
+                                "uppercase",
+                                "ascii_only",
+                            ];
+                            if string_only.contains(&key.as_str())
+                                && !matches!(field_type, FieldType::Str)
+                            {
+                                return Err(
+                                    meta.error(format!("'{}' only applies to String fields", key))
+                                );
+                            }
+
+                            let path_only = [
+                                "must_exist",
+                                "is_file",
+                                "is_dir",
+                                "readable",
+                                "writable",
+                                "create_dir",
+                            ];
+                            if path_only.contains(&key.as_str())
+                                && !matches!(field_type, FieldType::Path)
+                            {
+                                return Err(meta.error(format!(
+                                    "'{}' only applies to PathBuf fields",
+                                    key
+                                )));
+                            }
+
                             if let Ok(value) = meta.value() {
                                 let lit: Lit = value.parse()?;
 
                                 match (key.as_str(), lit) {
-                                    ("default", Lit::Str(s)) => {
-                                        meta_field.default = Some(s.value())
+                                    ("default", lit) => {
+                                        // Typed literals are kept in the same text form as `default = "..."`
+                                        let text = match (&lit, &field_type) {
+                                            (Lit::Str(s), _) => s.value(),
+                                            (
+                                                Lit::Int(i),
+                                                FieldType::Int(_)
+                                                | FieldType::Float
+                                                | FieldType::Duration
+                                                | FieldType::Bytes,
+                                            ) => i.base10_digits().to_string(),
+                                            (
+                                                Lit::Float(f),
+                                                FieldType::Float | FieldType::Duration,
+                                            ) => f.base10_digits().to_string(),
+                                            (Lit::Bool(b), FieldType::Bool) => b.value.to_string(),
+                                            _ => {
+                                                return Err(syn::Error::new_spanned(
+                                                    lit,
+                                                    "Default literal does not match the field's type",
+                                                ))
+                                            }
+                                        };
+                                        meta_field.default = Some(text);
+                                        default_lit = Some(lit);
                                     }
+                                    ("default_fn", Lit::Str(s)) => default_fn = Some(s.parse()?),
                                     ("min", Lit::Int(i)) => {
                                         meta_field.min = Some(i.base10_parse::<f64>()?)
                                     }
@@ -137,8 +612,68 @@ pub fn expand_derive_profig(input: DeriveInput) -> proc_macro2::TokenStream {
                                     ("max", Lit::Float(f)) => {
                                         meta_field.max = Some(f.base10_parse::<f64>()?)
                                     }
-                                    ("regex", Lit::Str(s)) => meta_field.regex = Some(s.value()),
+                                    ("min", Lit::Str(s)) => {
+                                        meta_field.min = Some(parse_unit(&s, &field_type)?)
+                                    }
+                                    ("max", Lit::Str(s)) => {
+                                        meta_field.max = Some(parse_unit(&s, &field_type)?)
+                                    }
+                                    ("regex", Lit::Str(s)) => {
+                                        regex::Regex::new(&s.value()).map_err(|e| {
+                                            syn::Error::new_spanned(
+                                                &s,
+                                                format!("Invalid regex: {}", e),
+                                            )
+                                        })?;
+                                        meta_field.regex = Some(s.value())
+                                    }
                                     ("doc", Lit::Str(s)) => meta_field.doc = Some(s.value()),
+                                    ("deprecated", Lit::Str(s)) => {
+                                        meta_field.deprecated = Some(s.value())
+                                    }
+                                    ("alias", Lit::Str(s)) => meta_field.aliases.push(s.value()),
+                                    ("format", Lit::Str(s)) => {
+                                        let format = s.value();
+                                        let valid = match field_type {
+                                            FieldType::Str => {
+                                                STRING_FORMATS.contains(&format.as_str())
+                                            }
+                                            FieldType::Int(_) => format == "port",
+                                            _ => false,
+                                        };
+                                        if !valid {
+                                            return Err(syn::Error::new_spanned(
+                                                s,
+                                                format!(
+                                                    "Unknown format '{}' for this field; expected one of: {} (strings) or port (integers)",
+                                                    format,
+                                                    STRING_FORMATS.join(", ")
+                                                ),
+                                            ));
+                                        }
+                                        meta_field.format = Some(format);
+                                    }
+                                    ("min_len", Lit::Int(i)) => {
+                                        meta_field.min_len = Some(i.base10_parse()?)
+                                    }
+                                    ("max_len", Lit::Int(i)) => {
+                                        meta_field.max_len = Some(i.base10_parse()?)
+                                    }
+                                    ("gt", Lit::Str(s)) => {
+                                        meta_field.gt = Some(s.value());
+                                        comparisons.push((s.clone(), field_type));
+                                        field_refs.push(s);
+                                    }
+                                    ("lt", Lit::Str(s)) => {
+                                        meta_field.lt = Some(s.value());
+                                        comparisons.push((s.clone(), field_type));
+                                        field_refs.push(s);
+                                    }
+                                    ("validate", Lit::Str(s)) => field_validators.push((
+                                        field.ident.clone().unwrap(),
+                                        s.parse()?,
+                                        optional,
+                                    )),
                                     _ => {
                                         return Err(syn::Error::new_spanned(
                                             meta.path,
@@ -149,6 +684,33 @@ pub fn expand_derive_profig(input: DeriveInput) -> proc_macro2::TokenStream {
                                         ));
                                     }
                                 }
+                            } else if key == "secret" {
+                                meta_field.secret = true;
+                            } else if key == "non_empty" {
+                                meta_field.non_empty = true;
+                            } else if key == "trim" {
+                                meta_field.trim = true;
+                            } else if key == "lowercase" || key == "uppercase" {
+                                if meta_field.lowercase || meta_field.uppercase {
+                                    return Err(meta
+                                        .error("'lowercase' and 'uppercase' cannot be combined"));
+                                }
+                                meta_field.lowercase = key == "lowercase";
+                                meta_field.uppercase = key == "uppercase";
+                            } else if key == "ascii_only" {
+                                meta_field.ascii_only = true;
+                            } else if key == "must_exist" {
+                                meta_field.must_exist = true;
+                            } else if key == "is_file" {
+                                meta_field.is_file = true;
+                            } else if key == "is_dir" {
+                                meta_field.is_dir = true;
+                            } else if key == "readable" {
+                                meta_field.readable = true;
+                            } else if key == "writable" {
+                                meta_field.writable = true;
+                            } else if key == "create_dir" {
+                                meta_field.create_dir = true;
                             } else {
                                 return Err(syn::Error::new_spanned(
                                     meta.path,
@@ -163,9 +725,92 @@ pub fn expand_derive_profig(input: DeriveInput) -> proc_macro2::TokenStream {
                             return e.to_compile_error();
                         }
 
+                        if let Some(lit) = &default_lit {
+                            if default_fn.is_some() {
+                                return syn::Error::new_spanned(
+                                    lit,
+                                    "'default' and 'default_fn' cannot be combined",
+                                )
+                                .to_compile_error();
+                            }
+                            let text = meta_field.default.as_deref().unwrap_or_default();
+                            if let Err(e) = check_default(text, &meta_field, &field_type) {
+                                return syn::Error::new_spanned(lit, e).to_compile_error();
+                            }
+                        }
+                        if let Some(path) = default_fn {
+                            default_fns.insert(field_name.clone(), path);
+                        }
+
+                        // An explicit `format` narrows the one implied by the type
+                        if meta_field.format.is_none() {
+                            meta_field.format = implied_format.map(str::to_string);
+                        }
+
+                        // `min = -1` on a `u32` can never be satisfied as written
+                        if let FieldType::Int(kind) = field_type {
+                            let (lo, hi) = kind.range();
+                            let outside = |b: f64| b < lo as f64 || b > hi as f64;
+                            if let Some(b) = meta_field
+                                .min
+                                .filter(|b| outside(*b))
+                                .or(meta_field.max.filter(|b| outside(*b)))
+                            {
+                                return syn::Error::new_spanned(
+                                    attr,
+                                    format!(
+                                        "Bound {} is outside the range of {} ({}..={})",
+                                        b,
+                                        kind.name(),
+                                        lo,
+                                        hi
+                                    ),
+                                )
+                                .to_compile_error();
+                            }
+                        }
+
+                        if meta_field.is_file && (meta_field.is_dir || meta_field.create_dir) {
+                            return syn::Error::new_spanned(
+                                attr,
+                                "'is_file' cannot be combined with 'is_dir' or 'create_dir'",
+                            )
+                            .to_compile_error();
+                        }
+
+                        if let (Some(min), Some(max)) = (meta_field.min, meta_field.max) {
+                            if min > max {
+                                let bound = |b: f64| match field_type {
+                                    FieldType::Duration => units::format_duration(b),
+                                    FieldType::Bytes => units::format_bytes(b as u64),
+                                    _ => b.to_string(),
+                                };
+                                return syn::Error::new_spanned(
+                                    attr,
+                                    format!(
+                                        "'min' ({}) is greater than 'max' ({})",
+                                        bound(min),
+                                        bound(max)
+                                    ),
+                                )
+                                .to_compile_error();
+                            }
+                        }
+
+                        if let (Some(min), Some(max)) = (meta_field.min_len, meta_field.max_len) {
+                            if min > max {
+                                return syn::Error::new_spanned(
+                                    attr,
+                                    format!("min_len ({}) is greater than max_len ({})", min, max),
+                                )
+                                .to_compile_error();
+                            }
+                        }
+
                         schema.push(FieldSchema {
                             name: field_name.clone(),
                             ty: field_type,
+                            optional,
                             metadata: meta_field,
                         });
                     }
@@ -174,15 +819,126 @@ pub fn expand_derive_profig(input: DeriveInput) -> proc_macro2::TokenStream {
         }
     }
 
+    // Cross-field rules must name fields of this struct; rule targets need a schema entry
+    let field_names: Vec<String> = match &input.data {
+        Data::Struct(data_struct) => data_struct
+            .fields
+            .iter()
+            .filter_map(|f| f.ident.as_ref().map(|i| i.to_string()))
+            .collect(),
+        _ => vec![],
+    };
+    for lit in &field_refs {
+        if !field_names.contains(&lit.value()) {
+            return syn::Error::new_spanned(lit, format!("Unknown field '{}'", lit.value()))
+                .to_compile_error();
+        }
+    }
+    for (lit, ty) in &comparisons {
+        let key = key_of(&lit.value());
+        if let Some(other) = schema.iter().find(|f| f.name == key) {
+            if !comparable(ty, &other.ty) {
+                return syn::Error::new_spanned(
+                    lit,
+                    format!(
+                        "Field '{}' cannot be compared with this field; both must be numbers, durations or byte sizes",
+                        lit.value()
+                    ),
+                )
+                .to_compile_error();
+            }
+        }
+    }
+    // `gt`/`lt` name Rust fields; the schema refers to their config keys
+    for f in &mut schema {
+        for other in [&mut f.metadata.gt, &mut f.metadata.lt]
+            .into_iter()
+            .flatten()
+        {
+            *other = key_of(other);
+        }
+    }
+    let rule_target = |schema: &[FieldSchema], ident: &syn::Ident| {
+        let name = ident.to_string();
+        if !field_names.contains(&name) {
+            return Err(syn::Error::new_spanned(
+                ident,
+                format!("Unknown field '{}'", name),
+            ));
+        }
+        let key = key_of(&name);
+        schema.iter().position(|f| f.name == key).ok_or_else(|| {
+            syn::Error::new_spanned(
+                ident,
+                format!(
+                    "Field '{}' needs a #[profig(...)] attribute to be used in a rule",
+                    name
+                ),
+            )
+        })
+    };
+
+    for (field, cond, value) in &require_if_rules {
+        let idx = match rule_target(&schema, field) {
+            Ok(idx) => idx,
+            Err(e) => return e.to_compile_error(),
+        };
+        if !field_names.contains(&cond.to_string()) {
+            return syn::Error::new_spanned(cond, format!("Unknown field '{}'", cond))
+                .to_compile_error();
+        }
+        let equals = match value.as_ref().map(lit_to_value).transpose() {
+            Ok(v) => v,
+            Err(e) => return e.to_compile_error(),
+        };
+        schema[idx].metadata.required_if.push(Condition {
+            field: key_of(&cond.to_string()),
+            equals,
+        });
+    }
+
+    for group in &exclusive_groups {
+        for field in group {
+            let idx = match rule_target(&schema, field) {
+                Ok(idx) => idx,
+                Err(e) => return e.to_compile_error(),
+            };
+            // A default would always count as set and make the group unsatisfiable
+            if schema[idx].metadata.default.is_some() || default_fns.contains_key(&schema[idx].name)
+            {
+                return syn::Error::new_spanned(
+                    field,
+                    format!(
+                        "Field '{}' is mutually exclusive and cannot have a default",
+                        field
+                    ),
+                )
+                .to_compile_error();
+            }
+            let others = group
+                .iter()
+                .filter(|o| *o != field)
+                .map(|o| key_of(&o.to_string()));
+            schema[idx].metadata.conflicts_with.extend(others);
+        }
+    }
+
     let schema_entries: Vec<_> = schema
         .iter()
         .map(|f| {
             let name = &f.name;
+            let optional = f.optional;
             let ty = match f.ty {
                 FieldType::Str => quote!(::profig::types::FieldType::Str),
-                FieldType::Int => quote!(::profig::types::FieldType::Int),
+                FieldType::Int(kind) => {
+                    let kind = format_ident!("{}", format!("{:?}", kind));
+                    quote!(::profig::types::FieldType::Int(::profig::types::IntKind::#kind))
+                }
                 FieldType::Float => quote!(::profig::types::FieldType::Float),
                 FieldType::Bool => quote!(::profig::types::FieldType::Bool),
+                FieldType::Duration => quote!(::profig::types::FieldType::Duration),
+                FieldType::Bytes => quote!(::profig::types::FieldType::Bytes),
+                FieldType::Path => quote!(::profig::types::FieldType::Path),
             };
 
             let MetaField {
@@ -191,13 +947,77 @@ pub fn expand_derive_profig(input: DeriveInput) -> proc_macro2::TokenStream {
                 max,
                 regex,
                 doc,
+                secret,
+                deprecated,
+                aliases,
+                required_if,
+                conflicts_with,
+                gt,
+                lt,
+                min_len,
+                max_len,
+                non_empty,
+                trim,
+                lowercase,
+                uppercase,
+                ascii_only,
+                format,
+                must_exist,
+                is_file,
+                is_dir,
+                readable,
+                writable,
+                create_dir,
             } = &f.metadata;
 
-            let default = match default {
+            let format = match format {
                 Some(v) => quote!(Some(#v.to_string())),
                 None => quote!(None),
             };
 
+            let min_len = match min_len {
+                Some(n) => quote!(Some(#n)),
+                None => quote!(None),
+            };
+
+            let max_len = match max_len {
+                Some(n) => quote!(Some(#n)),
+                None => quote!(None),
+            };
+
+            let required_if = required_if.iter().map(|c| {
+                let field = &c.field;
+                let equals = match &c.equals {
+                    Some(v) => {
+                        let v = value_tokens(v);
+                        quote!(Some(#v))
+                    }
+                    None => quote!(None),
+                };
+                quote! {
+                    ::profig::types::Condition {
+                        field: #field.to_string(),
+                        equals: #equals,
+                    }
+                }
+            });
+
+            let gt = match gt {
+                Some(v) => quote!(Some(#v.to_string())),
+                None => quote!(None),
+            };
+
+            let lt = match lt {
+                Some(v) => quote!(Some(#v.to_string())),
+                None => quote!(None),
+            };
+
+            let default = match (default_fns.get(name), default) {
+                (Some(path), _) => quote!(Some(::profig::validator::default_text(&#path(), &#ty))),
+                (None, Some(v)) => quote!(Some(#v.to_string())),
+                (None, None) => quote!(None),
+            };
+
             let regex = match regex {
                 Some(v) => quote!(Some(#v.to_string())),
                 None => quote!(None),
@@ -208,6 +1028,11 @@ pub fn expand_derive_profig(input: DeriveInput) -> proc_macro2::TokenStream {
                 None => quote!(None),
             };
 
+            let deprecated = match deprecated {
+                Some(v) => quote!(Some(#v.to_string())),
+                None => quote!(None),
+            };
+
             let min = match min {
                 Some(m) => quote!(Some(#m)),
                 None => quote!(None),
@@ -222,93 +1047,231 @@ pub fn expand_derive_profig(input: DeriveInput) -> proc_macro2::TokenStream {
                 ::profig::types::FieldSchema {
                     name: #name.to_string(),
                     ty: #ty,
+                    optional: #optional,
                     metadata: ::profig::types::MetaField {
                         default: #default,
                         min: #min,
                         max: #max,
                         regex: #regex,
                         doc: #doc,
+                        secret: #secret,
+                        deprecated: #deprecated,
+                        aliases: vec![#(#aliases.to_string()),*],
+                        required_if: vec![#(#required_if),*],
+                        conflicts_with: vec![#(#conflicts_with.to_string()),*],
+                        gt: #gt,
+                        lt: #lt,
+                        min_len: #min_len,
+                        max_len: #max_len,
+                        non_empty: #non_empty,
+                        trim: #trim,
+                        lowercase: #lowercase,
+                        uppercase: #uppercase,
+                        ascii_only: #ascii_only,
+                        format: #format,
+                        must_exist: #must_exist,
+                        is_file: #is_file,
+                        is_dir: #is_dir,
+                        readable: #readable,
+                        writable: #writable,
+                        create_dir: #create_dir,
                     }
                 }
             }
         })
         .collect();
-    let schema_entries_doc_gen = schema_entries.clone();
-    let schema_entries_sample_gen = schema_entries.clone();
-
-    let format_branches = formats.iter().map(|fmt| {
-        let ext_check = match fmt.as_str() {
-            "toml" => quote! { ext == "toml" },
-            "json" => quote! { ext == "json" },
-            "yaml" => quote! { ext == "yaml" },
-            _ => {
-                return syn::Error::new_spanned(&input, format!("Unsupported format: '{}'", fmt))
-                    .to_compile_error();
+
+    // Built-in names are checked here; custom ones are looked up in the registry at load time
+    let mut format_names = vec![];
+    for fmt in formats.iter().filter(|f| !f.is_empty()) {
+        match resolve_format(fmt) {
+            Some(f) => format_names.push(f.to_string()),
+            None => {
+                let known: Vec<_> = FORMATS.iter().map(|(name, _)| *name).collect();
+                return syn::Error::new_spanned(
+                    &input,
+                    format!(
+                        "Unsupported format: '{}' (built-in: {}; see custom_format)",
+                        fmt,
+                        known.join(", ")
+                    ),
+                )
+                .to_compile_error();
             }
-        };
+        }
+    }
+    format_names.extend(custom_formats);
+
+    let struct_name = name.to_string();
 
-        let loader_fn = match fmt.as_str() {
-            "toml" => quote! { ::profig::loader::toml::load_as_value },
-            "json" => quote! { ::profig::loader::json::load_as_value },
-            "yaml" | "yml" => quote! { ::profig::loader::yaml::load_as_value },
-            _ => quote! {},
+    // `#[profig(validate = "...")]` functions run on the deserialized value
+    let field_checks = field_validators.iter().map(|(ident, path, optional)| {
+        let label = key_of(&ident.to_string());
+        let call = if *optional {
+            quote! {
+                match &self.#ident {
+                    Some(v) => #path(v),
+                    None => Ok(()),
+                }
+            }
+        } else {
+            quote! { #path(&self.#ident) }
         };
 
         quote! {
-            if #ext_check {
-                obj = #loader_fn(path)?;
-            }
+            (#call).map_err(|e| ::profig::error::ProfigError::Validation(format!("{}. Field: '{}'", e, #label)))?;
+        }
+    });
+    let struct_checks = struct_validators.iter().map(|path| {
+        quote! {
+            #path(self).map_err(|e| ::profig::error::ProfigError::Validation(e.to_string()))?;
         }
     });
 
-    let struct_name = name.to_string();
+    // `#[profig(debug)]` generates a Debug impl that hides secret fields
+    let debug_impl = if redacted_debug {
+        let mut entries = vec![];
+        if let Data::Struct(data_struct) = &input.data {
+            for field in &data_struct.fields {
+                let Some(ident) = &field.ident else { continue };
+                let label = ident.to_string();
+                let key = key_of(&label);
+                let secret = schema.iter().any(|f| f.name == key && f.metadata.secret);
 
-    quote! {
-        impl #name {
-            pub fn load (path: &str) -> Result<Self, Box<dyn std::error::Error>> {
-                let ext = ::std::path::Path::new(path).extension().and_then(|s| s.to_str()).unwrap_or("").to_ascii_lowercase();
+                entries.push(if secret {
+                    quote! { .field(#label, &::profig::secret::Redacted) }
+                } else {
+                    quote! { .field(#label, &self.#ident) }
+                });
+            }
+        }
 
-                let mut obj = ::serde_json::Value::Null;
+        quote! {
+            impl ::std::fmt::Debug for #name {
+                fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                    f.debug_struct(#struct_name)
+                        #(#entries)*
+                        .finish()
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
 
-                #(#format_branches)*
+    // With profiles, the file holds `[default]`, `[production]`, ... sections
+    let select_profile = if profiles {
+        quote! {
+            let obj = ::profig::profile::select(obj, &::profig::profile::from_env(#profile_env))?;
+        }
+    } else {
+        quote! {}
+    };
 
-                if obj.is_null() {
-                    // return Err(format!("Unsupported or missing file extension: '{}'", ext).into());
-                    return Err(Box::new(::profig::error::ProfigError::InvalidFormat(format!("Unsupported or missing file extension: '{}'", ext))));
-                }
+    if let (None, Some(path)) = (version, &migrations) {
+        return syn::Error::new_spanned(path, "'migrations' requires 'version'").to_compile_error();
+    }
 
-                let schema_vec = vec![
-                    #(#schema_entries),*
-                ];
+    // Older documents are upgraded to `version` before anything else looks at them
+    let migration_steps = match &migrations {
+        Some(path) => quote! { #path },
+        None => quote! { &[] },
+    };
+    let migrate_fn = match version {
+        Some(v) => quote! {
+            fn migrate (obj: ::serde_json::Value) -> Result<::serde_json::Value, Box<dyn std::error::Error>> {
+                let (obj, _) = ::profig::migrate::migrate(obj, #v, #migration_steps)?;
+                Ok(obj)
+            }
+        },
+        None => quote! {},
+    };
+    let run_migrations = match version {
+        Some(_) => quote! {
+            let obj = <Self as ::profig::Profig>::migrate(obj)?;
+        },
+        None => quote! {},
+    };
+    let migrate_file_fn = match version {
+        Some(v) => quote! {
+            /// Upgrades the file at `path` to the current version in place.
+            /// Returns `true` if the file was rewritten.
+            pub fn migrate_file (path: &str) -> Result<bool, Box<dyn std::error::Error>> {
+                ::profig::migrate::migrate_file(path, &[#(#format_names),*], #v, #migration_steps)
+            }
+        },
+        None => quote! {},
+    };
 
-                let mut json_val = ::serde_json::to_value(&obj).map_err(|e| ::profig::error::ProfigError::Parse { format: "json", error: e.to_string() })?;
-                ::profig::validator::validate_fields(&mut json_val, &schema_vec)?;
+    let version_const = match version {
+        Some(v) => quote! { const VERSION: Option<u64> = Some(#v); },
+        None => quote! {},
+    };
 
-                let conf = ::serde_json::from_value(json_val).map_err(|e| ::profig::error::ProfigError::Parse { format: "json", error: e.to_string() })?;
+    let load_profile_fn = if profiles {
+        quote! {
+            /// Loads `path` and merges the `profile` section over `default`.
+            pub fn load_profile (path: &str, profile: &str) -> Result<Self, Box<dyn std::error::Error>> {
+                let (conf, warnings) = Self::load_profile_with_warnings(path, profile)?;
+                ::profig::deprecation::report(&warnings);
 
-                return Ok(conf);
+                Ok(conf)
             }
 
-            pub fn generate_docs (path: &str) -> Result<(), Box<dyn std::error::Error>> {
-                let schema_vec = vec![
-                    #(#schema_entries_doc_gen),*
-                ];
+            /// Like `load_profile`, but returns deprecation warnings instead of printing them.
+            pub fn load_profile_with_warnings (path: &str, profile: &str) -> Result<(Self, Vec<String>), Box<dyn std::error::Error>> {
+                let obj = ::profig::loader::load_as_value(path, &[#(#format_names),*])?;
+                #run_migrations
+                let obj = ::profig::profile::select(obj, profile)?;
 
-                // let docContent = ::profig::generator::generate_docs(path);
-                ::profig::generator::generate_doc(path, &schema_vec, #struct_name)?;
+                let coerce = #coerce || ::profig::loader::string_values(path, &[#(#format_names),*]);
+                <Self as ::profig::Profig>::load_from_value_in(obj, Some(&::profig::paths::base_dir(path)), coerce)
+            }
+        }
+    } else {
+        quote! {}
+    };
 
-                Ok(())
+    quote! {
+        impl ::profig::Profig for #name {
+            const NAME: &'static str = #struct_name;
+            const FORMATS: &'static [&'static str] = &[#(#format_names),*];
+            const COERCE: bool = #coerce;
+            const PROFILES: bool = #profiles;
+            #version_const
+
+            fn schema() -> &'static [::profig::types::FieldSchema] {
+                static SCHEMA: ::std::sync::OnceLock<Vec<::profig::types::FieldSchema>> = ::std::sync::OnceLock::new();
+
+                SCHEMA.get_or_init(|| vec![
+                    #(#schema_entries),*
+                ])
             }
 
-            pub fn sample_config (path: &str) -> Result<(), Box<dyn ::std::error::Error>> {
-                let schema_vec = vec![
-                    #(#schema_entries_sample_gen),*
-                ];
+            #migrate_fn
+
+            fn load_file (path: &str) -> Result<::serde_json::Value, Box<dyn std::error::Error>> {
+                let obj = ::profig::loader::load_as_value(path, &[#(#format_names),*])?;
+                #run_migrations
+                #select_profile
 
-                ::profig::generator::sample_conf(path, &schema_vec)?;
+                Ok(obj)
+            }
+
+            fn run_custom_validators (&self) -> Result<(), ::profig::error::ProfigError> {
+                #(#field_checks)*
+                #(#struct_checks)*
 
                 Ok(())
             }
         }
+
+        impl #name {
+            #load_profile_fn
+
+            #migrate_file_fn
+        }
+
+        #debug_impl
     }
 }
@@ -0,0 +1,305 @@
+use profig_commons::error::ProfigError;
+use profig_commons::types::{FieldSchema, FieldType};
+use serde_json::Value;
+
+use crate::coerce;
+use crate::merge::set_path;
+use crate::secret::shown;
+
+/// Result of parsing command-line arguments against a schema.
+#[derive(Debug, Default)]
+pub struct CliArgs {
+    /// Path given with `--config`, if any.
+    pub config: Option<String>,
+    /// Values given on the command line, to be merged over the loaded file.
+    pub overrides: Value,
+    /// Whether `-h`/`--help` was passed.
+    pub help: bool,
+}
+
+fn flag_name(field: &str) -> String {
+    field.replace('_', "-")
+}
+
+fn find_field<'a>(schema: &'a [FieldSchema], flag: &str) -> Option<&'a FieldSchema> {
+    schema
+        .iter()
+        .find(|f| f.name == flag || flag_name(&f.name) == flag)
+}
+
+fn type_hint(ty: &FieldType) -> &'static str {
+    match ty {
+        FieldType::Int(_) => "<INT>",
+        FieldType::Float => "<FLOAT>",
+        FieldType::Str => "<STRING>",
+        FieldType::Bool => "[BOOL]",
+        FieldType::Duration => "<DURATION>",
+        FieldType::Bytes => "<SIZE>",
+        FieldType::Path => "<PATH>",
+    }
+}
+
+/// Converts a command-line string into a JSON value of the given field type.
+fn typed_value(raw: &str, f: &FieldSchema, arg: &str) -> Result<Value, ProfigError> {
+    let invalid = |expected: &str| {
+        ProfigError::Validation(format!(
+            "Invalid value '{}' for '{}': expected {}",
+            shown(raw, &f.metadata),
+            arg,
+            expected
+        ))
+    };
+
+    match f.ty {
+        // Paths given on the command line are relative to the working directory
+        FieldType::Path => Ok(Value::String(
+            crate::paths::absolute(std::path::Path::new(raw))
+                .to_string_lossy()
+                .into_owned(),
+        )),
+        FieldType::Str | FieldType::Duration | FieldType::Bytes => {
+            Ok(Value::String(raw.to_string()))
+        }
+        FieldType::Int(_) => coerce::parse(raw, &f.ty).ok_or_else(|| invalid("an integer")),
+        FieldType::Float => coerce::parse(raw, &f.ty).ok_or_else(|| invalid("a number")),
+        FieldType::Bool => coerce::parse(raw, &f.ty).ok_or_else(|| invalid("a boolean")),
+    }
+}
+
+/// Best-effort typing for `--set` keys that are not part of the schema.
+fn inferred_value(raw: &str) -> Value {
+    match serde_json::from_str::<Value>(raw) {
+        Ok(v) if !v.is_string() => v,
+        _ => Value::String(raw.to_string()),
+    }
+}
+
+/// Parses arguments (without the program name) into a config path and overrides.
+///
+/// Supported forms:
+/// - `--config <path>`
+/// - `--<field> <value>` / `--<field>=<value>` for every schema field (underscores may be written as dashes)
+/// - `--<flag>` / `--no-<flag>` for bool fields
+/// - `--set <key.path>=<value>` for any value, including nested ones
+pub fn parse_args<I, S>(args: I, schema: &[FieldSchema]) -> Result<CliArgs, ProfigError>
+where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+{
+    let mut parsed = CliArgs {
+        overrides: Value::Object(serde_json::Map::new()),
+        ..Default::default()
+    };
+    let mut args = args.into_iter().map(Into::into);
+
+    while let Some(arg) = args.next() {
+        if arg == "-h" || arg == "--help" {
+            parsed.help = true;
+            continue;
+        }
+
+        let Some(body) = arg.strip_prefix("--") else {
+            return Err(ProfigError::Validation(format!(
+                "Unexpected argument '{}'",
+                arg
+            )));
+        };
+
+        let (name, inline) = match body.split_once('=') {
+            Some((n, v)) => (n.to_string(), Some(v.to_string())),
+            None => (body.to_string(), None),
+        };
+
+        let mut take_value = |inline: Option<String>| {
+            inline
+                .or_else(|| args.next())
+                .ok_or_else(|| ProfigError::Validation(format!("Missing value for '--{}'", name)))
+        };
+
+        if name == "config" {
+            parsed.config = Some(take_value(inline)?);
+            continue;
+        }
+
+        if name == "set" {
+            let assignment = take_value(inline)?;
+            let (key, raw) = assignment.split_once('=').ok_or_else(|| {
+                ProfigError::Validation(format!(
+                    "Expected KEY=VALUE for '--set', got '{}'",
+                    assignment
+                ))
+            })?;
+            let value = match schema.iter().find(|f| f.name == key) {
+                Some(f) => typed_value(raw, f, key)?,
+                None => inferred_value(raw),
+            };
+            set_path(&mut parsed.overrides, key, value);
+            continue;
+        }
+
+        if let Some(f) = find_field(schema, &name) {
+            let value = match (&f.ty, inline) {
+                (FieldType::Bool, None) => Value::Bool(true),
+                (_, inline) => typed_value(&take_value(inline)?, f, &arg)?,
+            };
+            set_path(&mut parsed.overrides, &f.name, value);
+            continue;
+        }
+
+        if let Some(f) = name
+            .strip_prefix("no-")
+            .and_then(|n| find_field(schema, n))
+            .filter(|f| matches!(f.ty, FieldType::Bool))
+        {
+            set_path(&mut parsed.overrides, &f.name, Value::Bool(false));
+            continue;
+        }
+
+        return Err(ProfigError::Validation(format!(
+            "Unknown option '--{}'",
+            name
+        )));
+    }
+
+    Ok(parsed)
+}
+
+/// Renders `--help` output for the options generated from `schema`.
+pub fn help_text(program: &str, default_config: &str, schema: &[FieldSchema]) -> String {
+    let mut rows = vec![(
+        "--config <PATH>".to_string(),
+        format!("Config file to load [default: {}]", default_config),
+    )];
+
+    for f in schema {
+        let meta = &f.metadata;
+        let mut desc = meta.doc.clone().unwrap_or_default();
+        if let Some(def) = &meta.default {
+            if !desc.is_empty() {
+                desc.push(' ');
+            }
+            desc.push_str(&format!("[default: {}]", shown(def, meta)));
+        }
+        if let Some(msg) = &meta.deprecated {
+            if !desc.is_empty() {
+                desc.push(' ');
+            }
+            desc.push_str(&format!("(deprecated: {})", msg));
+        }
+
+        rows.push((
+            format!("--{} {}", flag_name(&f.name), type_hint(&f.ty)),
+            desc,
+        ));
+    }
+
+    rows.push((
+        "--set <KEY=VALUE>".to_string(),
+        "Override any config value, e.g. server.port=9000".to_string(),
+    ));
+    rows.push(("-h, --help".to_string(), "Print help".to_string()));
+
+    let width = rows.iter().map(|(flag, _)| flag.len()).max().unwrap_or(0);
+    let mut out = format!("Usage: {} [OPTIONS]\n\nOptions:\n", program);
+    for (flag, desc) in rows {
+        out.push_str(&format!("  {:width$}  {}\n", flag, desc, width = width));
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::field;
+    use profig_commons::types::IntKind;
+    use serde_json::json;
+
+    fn schema() -> Vec<FieldSchema> {
+        vec![
+            field("max_threads", FieldType::Int(IntKind::U16)),
+            field("verbose", FieldType::Bool),
+            field("name", FieldType::Str),
+        ]
+    }
+
+    fn parse(args: &[&str]) -> Result<CliArgs, ProfigError> {
+        parse_args(args.iter().copied(), &schema())
+    }
+
+    fn error_of(args: &[&str]) -> String {
+        parse(args).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn field_values_as_separate_or_inline_arguments() {
+        let parsed = parse(&["--max-threads", "8", "--name=app"]).unwrap();
+
+        assert_eq!(parsed.overrides, json!({ "max_threads": 8, "name": "app" }));
+        assert_eq!(parsed.config, None);
+        assert!(!parsed.help);
+    }
+
+    #[test]
+    fn bool_flags_and_their_negation() {
+        assert_eq!(
+            parse(&["--verbose"]).unwrap().overrides,
+            json!({ "verbose": true })
+        );
+        assert_eq!(
+            parse(&["--no-verbose"]).unwrap().overrides,
+            json!({ "verbose": false })
+        );
+        assert_eq!(
+            parse(&["--verbose=off"]).unwrap().overrides,
+            json!({ "verbose": false })
+        );
+    }
+
+    #[test]
+    fn set_assigns_nested_and_typed_values() {
+        let parsed = parse(&["--set", "server.port=9000", "--set=max_threads=4"]).unwrap();
+
+        assert_eq!(
+            parsed.overrides,
+            json!({ "server": { "port": 9000 }, "max_threads": 4 })
+        );
+    }
+
+    #[test]
+    fn config_and_help() {
+        let parsed = parse(&["--config", "app.toml", "-h"]).unwrap();
+
+        assert_eq!(parsed.config.as_deref(), Some("app.toml"));
+        assert!(parsed.help);
+        assert!(parse(&["--help"]).unwrap().help);
+    }
+
+    #[test]
+    fn rejects_unknown_options_and_missing_values() {
+        assert_eq!(
+            error_of(&["--threads", "8"]),
+            "Validation Error: Unknown option '--threads'"
+        );
+        assert_eq!(
+            error_of(&["--name"]),
+            "Validation Error: Missing value for '--name'"
+        );
+        assert_eq!(
+            error_of(&["--config"]),
+            "Validation Error: Missing value for '--config'"
+        );
+        assert_eq!(
+            error_of(&["--set", "port"]),
+            "Validation Error: Expected KEY=VALUE for '--set', got 'port'"
+        );
+        assert_eq!(
+            error_of(&["app.toml"]),
+            "Validation Error: Unexpected argument 'app.toml'"
+        );
+        assert_eq!(
+            error_of(&["--max-threads", "eight"]),
+            "Validation Error: Invalid value 'eight' for '--max-threads': expected an integer"
+        );
+    }
+}
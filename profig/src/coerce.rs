@@ -0,0 +1,107 @@
+use profig_commons::types::{FieldSchema, FieldType};
+use serde_json::Value;
+
+/// Parses a string as a value of type `ty`, accepting the spellings string-only
+/// sources (environment variables, INI files, command-line arguments) use:
+/// `"8"`, `"1.5"`, and `true`/`false`, `yes`/`no`, `on`/`off`, `1`/`0` for booleans.
+///
+/// Returns `None` if `raw` doesn't parse, or for types that are read from strings
+/// anyway (`String`, paths, durations and byte sizes).
+pub fn parse(raw: &str, ty: &FieldType) -> Option<Value> {
+    let s = raw.trim();
+    match ty {
+        FieldType::Int(_) => s
+            .parse::<i64>()
+            .map(Value::from)
+            .or_else(|_| s.parse::<u64>().map(Value::from))
+            .ok(),
+        FieldType::Float => s
+            .parse::<f64>()
+            .ok()
+            .and_then(serde_json::Number::from_f64)
+            .map(Value::Number),
+        FieldType::Bool => match s.to_ascii_lowercase().as_str() {
+            "true" | "yes" | "on" | "1" => Some(Value::Bool(true)),
+            "false" | "no" | "off" | "0" => Some(Value::Bool(false)),
+            _ => None,
+        },
+        FieldType::Str | FieldType::Path | FieldType::Duration | FieldType::Bytes => None,
+    }
+}
+
+/// Converts string values of `Int`, `Float` and `Bool` fields to their types.
+///
+/// Strings that don't parse are left as they are, so the validator reports them
+/// as type mismatches.
+pub fn coerce(config: &mut Value, schema: &[FieldSchema]) {
+    let Some(map) = config.as_object_mut() else {
+        return;
+    };
+
+    for f in schema {
+        if let Some(v) = map.get_mut(&f.name) {
+            if let Some(parsed) = v.as_str().and_then(|s| parse(s, &f.ty)) {
+                *v = parsed;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::field;
+    use profig_commons::types::IntKind;
+    use serde_json::json;
+
+    #[test]
+    fn parses_string_spellings() {
+        let int = FieldType::Int(IntKind::I64);
+        assert_eq!(parse(" 8 ", &int), Some(json!(8)));
+        assert_eq!(parse("18446744073709551615", &int), Some(json!(u64::MAX)));
+        assert_eq!(parse("1.5", &FieldType::Float), Some(json!(1.5)));
+        for (raw, expected) in [("yes", true), ("ON", true), ("1", true), ("off", false)] {
+            assert_eq!(
+                parse(raw, &FieldType::Bool),
+                Some(json!(expected)),
+                "{}",
+                raw
+            );
+        }
+        assert_eq!(parse("eight", &int), None);
+        assert_eq!(parse("maybe", &FieldType::Bool), None);
+        assert_eq!(parse("8", &FieldType::Str), None);
+    }
+
+    #[test]
+    fn leaves_unparsable_and_unknown_values_alone() {
+        let schema = [
+            field("threads", FieldType::Int(IntKind::U16)),
+            field("verbose", FieldType::Bool),
+            field("name", FieldType::Str),
+        ];
+        let mut config = json!({ "threads": "eight", "verbose": "on", "name": "1", "other": "2" });
+        coerce(&mut config, &schema);
+
+        assert_eq!(
+            config,
+            json!({ "threads": "eight", "verbose": true, "name": "1", "other": "2" })
+        );
+    }
+
+    #[test]
+    fn runs_after_interpolation() {
+        // As in `load_from_value_in`: a string field referencing a string-typed
+        // number must get the string, not the coerced number
+        let schema = [
+            field("threads", FieldType::Int(IntKind::U16)),
+            field("label", FieldType::Str),
+            field("workers", FieldType::Int(IntKind::U16)),
+        ];
+        let mut config = json!({ "threads": "8", "label": "${threads}", "workers": "${threads}" });
+        crate::interpolate::interpolate(&mut config, &schema).unwrap();
+        coerce(&mut config, &schema);
+
+        assert_eq!(config, json!({ "threads": 8, "label": "8", "workers": 8 }));
+    }
+}
@@ -0,0 +1,359 @@
+use profig_commons::error::ProfigError;
+use profig_commons::types::FieldSchema;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use std::error::Error;
+use std::path::Path;
+
+use crate::{cli, deprecation, edit, generator, loader, merge, paths, validator};
+
+/// A config struct with a schema, implemented by `#[derive(Profig)]`.
+///
+/// Generic code can load any config through it:
+/// ```
+/// use profig::Profig;
+///
+/// fn start<C: Profig>(path: &str) -> Result<C, Box<dyn std::error::Error>> {
+///     C::load(path)
+/// }
+/// ```
+pub trait Profig: DeserializeOwned {
+    /// Struct name, used in generated docs and as the fallback program name in `--help`.
+    const NAME: &'static str;
+
+    /// Formats `load` accepts, from `#[profig(format = "...")]`.
+    const FORMATS: &'static [&'static str];
+
+    /// Whether string values are always coerced to the field types (`#[profig(coerce)]`).
+    const COERCE: bool = false;
+
+    /// Current schema version, from `#[profig(version = N)]`.
+    const VERSION: Option<u64> = None;
+
+    /// Whether the file is split into profile sections (`#[profig(profiles)]`).
+    const PROFILES: bool = false;
+
+    /// The field schema, built once on first use.
+    fn schema() -> &'static [FieldSchema];
+
+    /// Reads `path` and applies migrations and profile selection, before validation.
+    #[doc(hidden)]
+    fn load_file(path: &str) -> Result<Value, Box<dyn Error>>;
+
+    /// Upgrades a document to `VERSION` with the `#[profig(migrations = "...")]` steps.
+    #[doc(hidden)]
+    fn migrate(obj: Value) -> Result<Value, Box<dyn Error>> {
+        Ok(obj)
+    }
+
+    /// Runs the `#[profig(validate = "...")]` functions, fields first.
+    #[doc(hidden)]
+    fn run_custom_validators(&self) -> Result<(), ProfigError>;
+
+    fn load(path: &str) -> Result<Self, Box<dyn Error>> {
+        let (conf, warnings) = Self::load_with_warnings(path)?;
+        deprecation::report(&warnings);
+
+        Ok(conf)
+    }
+
+    /// Like `load`, but returns deprecation warnings instead of printing them.
+    fn load_with_warnings(path: &str) -> Result<(Self, Vec<String>), Box<dyn Error>> {
+        let obj = Self::load_file(path)?;
+        let coerce = Self::COERCE || loader::string_values(path, Self::FORMATS);
+
+        Self::load_from_value_in(obj, Some(&paths::base_dir(path)), coerce)
+    }
+
+    /// Like `load`, but string values of integer, float and bool fields are
+    /// converted to their types first (`"8"`, `"1.5"`, `"yes"`).
+    fn load_lenient(path: &str) -> Result<Self, Box<dyn Error>> {
+        let (conf, warnings) = Self::load_lenient_with_warnings(path)?;
+        deprecation::report(&warnings);
+
+        Ok(conf)
+    }
+
+    /// Like `load_lenient`, but returns deprecation warnings instead of printing them.
+    fn load_lenient_with_warnings(path: &str) -> Result<(Self, Vec<String>), Box<dyn Error>> {
+        let obj = Self::load_file(path)?;
+
+        Self::load_from_value_in(obj, Some(&paths::base_dir(path)), true)
+    }
+
+    fn load_from_value(obj: Value) -> Result<Self, Box<dyn Error>> {
+        let (conf, warnings) = Self::load_from_value_with_warnings(obj)?;
+        deprecation::report(&warnings);
+
+        Ok(conf)
+    }
+
+    fn load_from_value_with_warnings(obj: Value) -> Result<(Self, Vec<String>), Box<dyn Error>> {
+        Self::load_from_value_in(obj, None, Self::COERCE)
+    }
+
+    /// Like `load_from_value`, but string values are converted to the field types
+    /// first, for values built from environment variables or other string-only sources.
+    fn load_from_value_lenient(obj: Value) -> Result<Self, Box<dyn Error>> {
+        let (conf, warnings) = Self::load_from_value_lenient_with_warnings(obj)?;
+        deprecation::report(&warnings);
+
+        Ok(conf)
+    }
+
+    /// Like `load_from_value_lenient`, but returns deprecation warnings instead of printing them.
+    fn load_from_value_lenient_with_warnings(
+        obj: Value,
+    ) -> Result<(Self, Vec<String>), Box<dyn Error>> {
+        Self::load_from_value_in(obj, None, true)
+    }
+
+    /// Like `load_from_value_with_warnings`, but relative `PathBuf` fields are resolved
+    /// against `base_dir` (usually the config file's directory) instead of the working directory,
+    /// and string values are converted to the field types if `coerce` is set.
+    ///
+    /// Values from older schema versions are migrated first; the `version` key itself
+    /// is not passed on to serde.
+    fn load_from_value_in(
+        obj: Value,
+        base_dir: Option<&Path>,
+        coerce: bool,
+    ) -> Result<(Self, Vec<String>), Box<dyn Error>> {
+        let (conf, warnings, val) = Self::check_value(obj, base_dir, coerce)?;
+        paths::create_dirs(&val, Self::schema())?;
+
+        Ok((conf, warnings))
+    }
+
+    /// Everything `load_from_value_in` does except creating `create_dir` directories.
+    /// Also returns the validated value the config was deserialized from.
+    #[doc(hidden)]
+    fn check_value(
+        obj: Value,
+        base_dir: Option<&Path>,
+        coerce: bool,
+    ) -> Result<(Self, Vec<String>, Value), Box<dyn Error>> {
+        let schema = Self::schema();
+
+        let mut json_val = Self::migrate(obj)?;
+        if let (Some(_), Some(map)) = (Self::VERSION, json_val.as_object_mut()) {
+            map.remove(crate::migrate::VERSION_KEY);
+        }
+        let warnings = prepare_value(&mut json_val, schema, base_dir, coerce)?;
+
+        let conf = <Self as serde::Deserialize>::deserialize(&json_val).map_err(|e| {
+            ProfigError::Parse {
+                format: "json",
+                // serde quotes offending values, which may be secrets
+                error: crate::secret::scrub(&e.to_string(), &json_val, schema),
+            }
+        })?;
+        conf.run_custom_validators()?;
+
+        Ok((conf, warnings, json_val))
+    }
+
+    /// Loads the config file given by `--config` (or `default_path`) and applies
+    /// command-line overrides from `std::env::args()` on top of it.
+    fn load_with_args(default_path: &str) -> Result<Self, Box<dyn Error>> {
+        let mut args = std::env::args();
+        let program = args.next().unwrap_or_else(|| Self::NAME.to_string());
+
+        Self::load_from_args(&program, default_path, args)
+    }
+
+    /// Like `load_with_args`, but returns deprecation warnings instead of printing them.
+    fn load_with_args_with_warnings(
+        default_path: &str,
+    ) -> Result<(Self, Vec<String>), Box<dyn Error>> {
+        let mut args = std::env::args();
+        let program = args.next().unwrap_or_else(|| Self::NAME.to_string());
+
+        Self::load_from_args_with_warnings(&program, default_path, args)
+    }
+
+    /// Like `load_with_args`, with the arguments passed in. On `-h`/`--help` this
+    /// returns `ProfigError::Help` with the rendered options instead of loading.
+    fn load_from_args<I, S>(
+        program: &str,
+        default_path: &str,
+        args: I,
+    ) -> Result<Self, Box<dyn Error>>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let (conf, warnings) = Self::load_from_args_with_warnings(program, default_path, args)?;
+        deprecation::report(&warnings);
+
+        Ok(conf)
+    }
+
+    /// Like `load_from_args`, but returns deprecation warnings instead of printing them.
+    fn load_from_args_with_warnings<I, S>(
+        program: &str,
+        default_path: &str,
+        args: I,
+    ) -> Result<(Self, Vec<String>), Box<dyn Error>>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let schema = Self::schema();
+
+        let parsed = cli::parse_args(args, schema)?;
+        if parsed.help {
+            let text = cli::help_text(program, default_path, schema);
+            return Err(Box::new(ProfigError::Help(text)));
+        }
+
+        let path = parsed.config.as_deref().unwrap_or(default_path);
+        let mut obj = Self::load_file(path)?;
+        merge::deep_merge(&mut obj, parsed.overrides);
+
+        let coerce = Self::COERCE || loader::string_values(path, Self::FORMATS);
+
+        Self::load_from_value_in(obj, Some(&paths::base_dir(path)), coerce)
+    }
+
+    /// Validates the config and writes it to `path` atomically, in the format
+    /// picked from the extension.
+    ///
+    /// Values the file being replaced wrote as `${...}` references are written as
+    /// references again if they haven't changed.
+    ///
+    /// Not available with `#[profig(profiles)]`: a loaded config is one merged
+    /// profile and can't be split back into sections. Not available either over a
+    /// file with includes, whose values would be copied into it. Use `edit` instead.
+    fn save(&self, path: &str) -> Result<(), Box<dyn Error>>
+    where
+        Self: Serialize,
+    {
+        if Self::PROFILES {
+            return Err(Box::new(ProfigError::Custom(format!(
+                "{} uses profiles; save would flatten the profile sections of '{}', use edit to change single keys",
+                Self::NAME,
+                path
+            ))));
+        }
+        let schema = Self::schema();
+        let existing = loader::existing_value(path);
+        if existing.as_ref().is_some_and(crate::include::uses_includes) {
+            return Err(Box::new(ProfigError::Custom(format!(
+                "'{}' includes other files; save would copy their values into it, use edit to change single keys",
+                path
+            ))));
+        }
+
+        let mut val = saved_value(self, schema)?;
+        if let (Some(v), Some(map)) = (Self::VERSION, val.as_object_mut()) {
+            map.insert(crate::migrate::VERSION_KEY.to_string(), Value::from(v));
+        }
+        validator::validate_fields(&mut val.clone(), schema)?;
+        self.run_custom_validators()?;
+        if let Some(existing) = &existing {
+            crate::secret::keep_files(&mut val, schema, existing, &paths::base_dir(path))?;
+
+            let mut refs = vec![];
+            crate::interpolate::string_paths(existing, "", &mut refs);
+            if !refs.is_empty() {
+                // What the references resolve to now, to tell which values are unchanged
+                let coerce = Self::COERCE || loader::string_values(path, Self::FORMATS);
+                let previous = Self::load_file(path)
+                    .and_then(|obj| Self::check_value(obj, Some(&paths::base_dir(path)), coerce));
+                if let Ok((previous, _, _)) = previous {
+                    let previous = saved_value(&previous, schema)?;
+                    crate::interpolate::keep_references(&mut val, &previous, existing);
+                }
+            }
+        }
+        paths::relativize(&mut val, schema, &paths::base_dir(path), existing.as_ref());
+
+        loader::save_value(path, &val)?;
+
+        Ok(())
+    }
+
+    /// Sets `key` (dotted, e.g. `server.port`) in the file at `path` after validating
+    /// the change, keeping comments and formatting where the format allows it.
+    ///
+    /// The edited document goes through the same steps as `load` (includes,
+    /// migrations, aliases, ...); with profiles, every profile is checked.
+    fn edit(path: &str, key: &str, value: impl Into<Value>) -> Result<(), Box<dyn Error>> {
+        loader::format_for_path(path, Self::FORMATS)?;
+        let coerce = Self::COERCE || loader::string_values(path, Self::FORMATS);
+        let base_dir = paths::base_dir(path);
+
+        edit::set_with(path, key, value, |doc| {
+            let doc = Self::migrate(crate::include::resolve(doc, path, Self::FORMATS)?)?;
+            let docs = if Self::PROFILES {
+                crate::profile::names(&doc)
+                    .iter()
+                    .map(|p| crate::profile::select(doc.clone(), p))
+                    .collect::<Result<Vec<_>, _>>()?
+            } else {
+                vec![doc]
+            };
+            for doc in docs {
+                Self::check_value(doc, Some(&base_dir), coerce)?;
+            }
+
+            Ok(())
+        })
+    }
+
+    fn generate_docs(path: &str) -> Result<(), Box<dyn Error>> {
+        generator::generate_doc(path, Self::schema(), Self::NAME)?;
+
+        Ok(())
+    }
+
+    fn export_schema(path: &str) -> Result<(), Box<dyn Error>> {
+        generator::json_schema(path, Self::schema(), Self::NAME)?;
+
+        Ok(())
+    }
+
+    fn sample_config(path: &str) -> Result<(), Box<dyn Error>> {
+        generator::sample_conf(path, Self::schema())?;
+
+        Ok(())
+    }
+}
+
+/// `conf` as `save` writes it, with durations and byte sizes in human units.
+fn saved_value<T: Serialize>(conf: &T, schema: &[FieldSchema]) -> Result<Value, ProfigError> {
+    let mut val = serde_json::to_value(conf).map_err(|e| ProfigError::Parse {
+        format: "json",
+        error: e.to_string(),
+    })?;
+    crate::units::humanize(&mut val, schema);
+
+    Ok(val)
+}
+
+/// The schema-driven steps every load applies to a raw value before deserializing it:
+/// renames and deprecations, interpolation, coercion (if `coerce` is set), `<field>_file`
+/// secrets, path resolution against `base_dir`, and field validation.
+///
+/// Returns the deprecation warnings. `profig validate` uses it for schema files.
+pub fn prepare_value(
+    val: &mut Value,
+    schema: &[FieldSchema],
+    base_dir: Option<&Path>,
+    coerce: bool,
+) -> Result<Vec<String>, Box<dyn Error>> {
+    let warnings = deprecation::apply(val, schema);
+    crate::interpolate::interpolate(val, schema)?;
+    if coerce {
+        crate::coerce::coerce(val, schema);
+    }
+    crate::secret::resolve_files(val, schema, base_dir)?;
+    if let Some(dir) = base_dir {
+        paths::resolve(val, schema, dir)?;
+    }
+    validator::validate_fields(val, schema)?;
+
+    Ok(warnings)
+}
@@ -0,0 +1,47 @@
+use profig_commons::types::FieldSchema;
+use serde_json::Value;
+
+/// Moves values under deprecated aliases to their field and collects a warning
+/// for every alias or deprecated field found in `config`.
+pub fn apply(config: &mut Value, schema: &[FieldSchema]) -> Vec<String> {
+    let mut warnings = vec![];
+    let Some(map) = config.as_object_mut() else {
+        return warnings;
+    };
+
+    for f in schema {
+        for alias in &f.metadata.aliases {
+            let Some(value) = map.remove(alias) else {
+                continue;
+            };
+
+            if map.get(&f.name).is_some_and(|v| !v.is_null()) {
+                warnings.push(format!(
+                    "'{}' is a deprecated name for '{}' and was ignored because '{}' is also set",
+                    alias, f.name, f.name
+                ));
+            } else {
+                warnings.push(format!(
+                    "'{}' is deprecated; rename it to '{}'",
+                    alias, f.name
+                ));
+                map.insert(f.name.clone(), value);
+            }
+        }
+
+        if let Some(msg) = &f.metadata.deprecated {
+            if map.get(&f.name).is_some_and(|v| !v.is_null()) {
+                warnings.push(format!("'{}' is deprecated: {}", f.name, msg));
+            }
+        }
+    }
+
+    warnings
+}
+
+/// Prints warnings to stderr; used by the generated `load` functions.
+pub fn report(warnings: &[String]) {
+    for w in warnings {
+        eprintln!("warning: {}", w);
+    }
+}
@@ -0,0 +1,490 @@
+use profig_commons::error::ProfigError;
+use profig_commons::types::FieldSchema;
+use serde_json::Value;
+use std::error::Error;
+
+use crate::format;
+use crate::loader::{extension_of, write_atomic};
+use crate::merge::set_path;
+
+/// Sets the value at a dotted `key` (`server.port`) in the config file at `path`.
+///
+/// The changed document's fields are validated against `schema` before anything
+/// is written; `Profig::edit` runs the full load pipeline instead (see [`set_with`]).
+/// TOML and YAML files keep their comments, key order and formatting; other
+/// formats are rewritten from the parsed document. The file is written atomically.
+pub fn set(
+    path: &str,
+    key: &str,
+    value: impl Into<Value>,
+    schema: &[FieldSchema],
+) -> Result<(), Box<dyn Error>> {
+    let string_values =
+        format::find_by_extension(&extension_of(path)).is_some_and(|fmt| fmt.string_values());
+
+    set_with(path, key, value, |mut doc| {
+        crate::interpolate::interpolate(&mut doc, schema)?;
+        if string_values {
+            crate::coerce::coerce(&mut doc, schema);
+        }
+        crate::paths::resolve(&mut doc, schema, &crate::paths::base_dir(path))?;
+        crate::validator::validate_fields(&mut doc, schema)?;
+
+        Ok(())
+    })
+}
+
+/// Like [`set`], but the edited document is passed to `check` instead, and
+/// nothing is written if it returns an error.
+pub fn set_with(
+    path: &str,
+    key: &str,
+    value: impl Into<Value>,
+    check: impl FnOnce(Value) -> Result<(), Box<dyn Error>>,
+) -> Result<(), Box<dyn Error>> {
+    let value = value.into();
+    let ext = extension_of(path);
+    let fmt = format::find_by_extension(&ext).ok_or_else(|| {
+        ProfigError::InvalidFormat(format!("Unsupported or missing file extension: '{}'", ext))
+    })?;
+
+    let content = std::fs::read_to_string(path).map_err(ProfigError::from)?;
+    let mut doc = fmt.parse(&content)?;
+    set_path(&mut doc, key, value.clone());
+    check(doc.clone())?;
+
+    let edited = match fmt.name() {
+        #[cfg(feature = "toml")]
+        "toml" => Some(toml::set(&content, key, &value)?),
+        #[cfg(feature = "yaml")]
+        "yaml" => yaml::set(&content, key, &value),
+        _ => None,
+    };
+
+    let updated = match edited {
+        Some(updated) => updated,
+        None => fmt.serialize(&doc)?,
+    };
+    // A textual edit that doesn't read back as the validated document never reaches the file
+    if fmt.parse(&updated)? != doc {
+        return Err(Box::new(ProfigError::Custom(format!(
+            "Editing '{}' in '{}' would not produce the expected document; nothing was written",
+            key, path
+        ))));
+    }
+    write_atomic(path, &updated)?;
+
+    Ok(())
+}
+
+/// Returns `content` of a TOML file in canonical form: keys, tables and comments
+/// stay where they are, while spacing around `=`, indentation, trailing comments
+/// and runs of blank lines are normalized.
+#[cfg(feature = "toml")]
+pub fn format_toml(content: &str) -> Result<String, ProfigError> {
+    toml::format(content)
+}
+
+#[cfg(feature = "toml")]
+mod toml {
+    use profig_commons::error::ProfigError;
+    use serde_json::Value;
+    use toml_edit::{Decor, DocumentMut, Item, Table, TableLike};
+
+    fn edit_error(msg: String) -> ProfigError {
+        ProfigError::Parse {
+            format: "toml",
+            error: msg,
+        }
+    }
+
+    fn to_toml(value: &Value) -> Result<toml_edit::Value, ProfigError> {
+        Ok(match value {
+            Value::Null => return Err(edit_error("TOML cannot represent null".to_string())),
+            Value::Bool(b) => (*b).into(),
+            Value::Number(n) => match (n.as_i64(), n.as_f64()) {
+                (Some(i), _) => i.into(),
+                (None, Some(f)) if !n.is_u64() => f.into(),
+                _ => return Err(edit_error(format!("{} is out of range for TOML", n))),
+            },
+            Value::String(s) => s.as_str().into(),
+            Value::Array(items) => {
+                let mut arr = toml_edit::Array::new();
+                for item in items {
+                    arr.push(to_toml(item)?);
+                }
+                arr.into()
+            }
+            Value::Object(map) => {
+                let mut table = toml_edit::InlineTable::new();
+                for (k, v) in map {
+                    table.insert(k, to_toml(v)?);
+                }
+                table.into()
+            }
+        })
+    }
+
+    /// Sets `key` in a TOML document, keeping the decoration of an existing value.
+    pub fn set(content: &str, key: &str, value: &Value) -> Result<String, ProfigError> {
+        let mut doc: DocumentMut = content
+            .parse()
+            .map_err(|e: toml_edit::TomlError| edit_error(e.to_string()))?;
+
+        let parts: Vec<&str> = key.split('.').collect();
+        let (last, parents) = parts.split_last().unwrap();
+
+        let mut table: &mut dyn TableLike = doc.as_table_mut();
+        for part in parents {
+            if !table.contains_key(part) {
+                table.insert(part, Item::Table(Table::new()));
+            }
+            table = table
+                .get_mut(part)
+                .and_then(Item::as_table_like_mut)
+                .ok_or_else(|| edit_error(format!("'{}' in '{}' is not a table", part, key)))?;
+        }
+
+        let new = to_toml(value)?;
+        match table.get_mut(last) {
+            Some(Item::Value(old)) => {
+                let decor = old.decor().clone();
+                *old = new;
+                *old.decor_mut() = decor;
+            }
+            Some(item) => *item = Item::Value(new),
+            None => {
+                table.insert(last, Item::Value(new));
+            }
+        }
+
+        Ok(doc.to_string())
+    }
+
+    /// Comment lines of a decor prefix, without indentation, keeping single
+    /// blank lines between them. `blank_first` forces a blank line in front.
+    fn lines_of(raw: &str, blank_first: bool) -> String {
+        let mut lines = raw.split('\n').collect::<Vec<_>>();
+        // The last piece is the indentation in front of the key itself
+        lines.pop();
+
+        let mut out = String::new();
+        let mut blank = blank_first;
+        for line in lines {
+            let line = line.trim();
+            if line.is_empty() {
+                blank = true;
+                continue;
+            }
+            if blank {
+                out.push('\n');
+                blank = false;
+            }
+            out.push_str(line);
+            out.push('\n');
+        }
+        if blank {
+            out.push('\n');
+        }
+
+        out
+    }
+
+    fn prefix_of(decor: &Decor) -> &str {
+        decor.prefix().and_then(|p| p.as_str()).unwrap_or("")
+    }
+
+    /// A trailing `# comment` after a value or header, with one space in front.
+    fn trailing_comment(decor: &Decor) -> String {
+        let suffix = decor.suffix().and_then(|s| s.as_str()).unwrap_or("").trim();
+        if suffix.is_empty() {
+            String::new()
+        } else {
+            format!(" {}", suffix)
+        }
+    }
+
+    fn format_table(table: &mut Table) {
+        for (mut key, item) in table.iter_mut() {
+            match item {
+                Item::Value(value) => {
+                    let prefix = lines_of(prefix_of(key.leaf_decor()), false);
+                    key.leaf_decor_mut().set_prefix(prefix);
+                    key.leaf_decor_mut().set_suffix(" ");
+
+                    let comment = trailing_comment(value.decor());
+                    value.decor_mut().set_prefix(" ");
+                    value.decor_mut().set_suffix(comment);
+                }
+                Item::Table(sub) => format_header(sub),
+                Item::ArrayOfTables(tables) => tables.iter_mut().for_each(format_header),
+                Item::None => {}
+            }
+        }
+    }
+
+    fn format_header(table: &mut Table) {
+        if !table.is_implicit() {
+            let prefix = lines_of(prefix_of(table.decor()), true);
+            let comment = trailing_comment(table.decor());
+            table.decor_mut().set_prefix(prefix);
+            table.decor_mut().set_suffix(comment);
+        }
+        format_table(table);
+    }
+
+    pub fn format(content: &str) -> Result<String, ProfigError> {
+        let mut doc: DocumentMut = content
+            .parse()
+            .map_err(|e: toml_edit::TomlError| edit_error(e.to_string()))?;
+
+        format_table(doc.as_table_mut());
+        let trailing = lines_of(
+            &format!("{}\n", doc.trailing().as_str().unwrap_or("")),
+            false,
+        );
+        doc.set_trailing(trailing.trim_end());
+
+        let formatted = doc.to_string();
+        let formatted = formatted.trim();
+        if formatted.is_empty() {
+            return Ok(String::new());
+        }
+
+        Ok(format!("{}\n", formatted))
+    }
+}
+
+#[cfg(feature = "yaml")]
+mod yaml {
+    use serde_json::Value;
+
+    /// Splits a YAML line into its `key:` part and the rest (value and comment).
+    fn split_key(line: &str) -> Option<(usize, &str, &str)> {
+        let indent = line.len() - line.trim_start().len();
+        let body = &line[indent..];
+        if body.starts_with('#') || body.starts_with('-') {
+            return None;
+        }
+
+        let colon = body.find(':')?;
+        let key = body[..colon].trim().trim_matches(['"', '\'']);
+        let rest = &body[colon + 1..];
+        if key.is_empty() || !(rest.is_empty() || rest.starts_with(' ')) {
+            return None;
+        }
+
+        Some((indent, key, rest))
+    }
+
+    /// Byte offset of a trailing ` # comment` outside quotes, if any.
+    fn comment_start(rest: &str) -> Option<usize> {
+        let mut quote = None;
+        let mut prev = ' ';
+        for (i, c) in rest.char_indices() {
+            match (quote, c) {
+                (None, '"' | '\'') => quote = Some(c),
+                (Some(q), c) if c == q => quote = None,
+                (None, '#') if prev.is_whitespace() => return Some(i),
+                _ => {}
+            }
+            prev = c;
+        }
+        None
+    }
+
+    /// The value as a one-line YAML scalar; `None` for collections and for
+    /// strings serde_yaml writes as multi-line block scalars.
+    fn render(value: &Value) -> Option<String> {
+        match value {
+            Value::Object(_) | Value::Array(_) => None,
+            v => serde_yaml::to_string(v)
+                .ok()
+                .map(|s| s.trim_end().to_string())
+                .filter(|s| !s.contains('\n')),
+        }
+    }
+
+    /// Sets a scalar at `key` in a block-style YAML document by editing the
+    /// matching line in place. Returns `None` when the document can't be edited
+    /// textually (non-scalar values, flow structures, keys inside sequences).
+    pub fn set(content: &str, key: &str, value: &Value) -> Option<String> {
+        let rendered = render(value)?;
+        let parts: Vec<&str> = key.split('.').collect();
+        let mut lines: Vec<String> = content.lines().map(str::to_string).collect();
+
+        // Path of mapping keys leading to the current line, with their indentation
+        let mut stack: Vec<(usize, String)> = vec![];
+        // Deepest ancestor of `key` found so far: (depth, indentation)
+        let mut ancestor: Option<(usize, usize)> = None;
+        let mut ancestor_end = None;
+        // Indentation of the `-` items of the sequence being skipped
+        let mut sequence: Option<usize> = None;
+
+        for i in 0..lines.len() {
+            let t = lines[i].trim_start();
+            let indent = lines[i].len() - t.len();
+            if t.is_empty() || t.starts_with('#') {
+                continue;
+            }
+            // Sequence items (and everything nested in them) aren't part of the key path
+            if let Some(seq) = sequence {
+                if indent > seq || (indent == seq && t.starts_with('-')) {
+                    continue;
+                }
+                sequence = None;
+            }
+            if t.starts_with('-') {
+                // Compact sequences (`key:` followed by `- item` at the same
+                // indentation) belong to the key at that indentation too
+                while stack.last().is_some_and(|(ind, _)| *ind > indent) {
+                    stack.pop();
+                }
+                let path: Vec<&str> = stack.iter().map(|(_, k)| k.as_str()).collect();
+                if parts.len() > path.len() && parts.starts_with(&path) {
+                    return None;
+                }
+                sequence = Some(indent);
+                continue;
+            }
+
+            // Anything else (multi-line scalars, flow syntax) is left to a full rewrite
+            let (indent, line_key, rest) = split_key(&lines[i])?;
+            let (line_key, rest) = (line_key.to_string(), rest.to_string());
+
+            while stack.last().is_some_and(|(ind, _)| *ind >= indent) {
+                stack.pop();
+            }
+            if let Some((_, anc_indent)) = ancestor {
+                if ancestor_end.is_none() && indent <= anc_indent {
+                    ancestor_end = Some(i);
+                }
+            }
+            stack.push((indent, line_key));
+
+            let path: Vec<&str> = stack.iter().map(|(_, k)| k.as_str()).collect();
+            if path == parts {
+                let value_part = rest.trim_start();
+                if value_part.is_empty()
+                    || value_part.starts_with(['|', '>', '{', '[', '&', '*', '!'])
+                {
+                    return None;
+                }
+
+                let prefix = &lines[i][..lines[i].len() - rest.len()];
+                lines[i] = match comment_start(&rest) {
+                    // Keep the comment and the spacing in front of it
+                    Some(c) => {
+                        let gap_start = rest[..c].trim_end().len();
+                        format!("{} {}{}", prefix, rendered, &rest[gap_start..])
+                    }
+                    None => format!("{} {}", prefix, rendered),
+                };
+                return Some(lines.join("\n") + "\n");
+            }
+
+            if path.len() < parts.len()
+                && parts.starts_with(&path)
+                && rest.trim().is_empty()
+                && ancestor.is_none_or(|(depth, _)| path.len() > depth)
+            {
+                ancestor = Some((path.len(), indent));
+                ancestor_end = None;
+            }
+        }
+
+        // Not found: add the missing keys below the deepest existing ancestor
+        let (depth, insert_at, base_indent) = match ancestor {
+            Some((depth, indent)) => (depth, ancestor_end.unwrap_or(lines.len()), indent + 2),
+            None => (0, lines.len(), 0),
+        };
+        let insert_at = (0..insert_at)
+            .rev()
+            .find(|&j| !lines[j].trim().is_empty())
+            .map_or(insert_at, |j| j + 1);
+
+        let mut new_lines = vec![];
+        for (n, part) in parts[depth..].iter().enumerate() {
+            let pad = " ".repeat(base_indent + n * 2);
+            if depth + n + 1 == parts.len() {
+                new_lines.push(format!("{}{}: {}", pad, part, rendered));
+            } else {
+                new_lines.push(format!("{}{}:", pad, part));
+            }
+        }
+        lines.splice(insert_at..insert_at, new_lines);
+
+        Some(lines.join("\n") + "\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "yaml")]
+    use serde_json::{json, Value};
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn yaml_keys_after_a_sequence_are_edited_in_place() {
+        let content = "servers:\n  - name: a\n    port: 1\n  - name: b\nport: 5 # main\n";
+
+        let updated = super::yaml::set(content, "port", &json!(6)).unwrap();
+
+        assert_eq!(
+            updated,
+            "servers:\n  - name: a\n    port: 1\n  - name: b\nport: 6 # main\n"
+        );
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn yaml_keys_inside_sequence_items_are_not_matched() {
+        let content = "servers:\n  - name: a\n    port: 1\n";
+        let compact = "servers:\n- name: a\n  port: 1\n";
+
+        assert_eq!(super::yaml::set(content, "servers.port", &json!(2)), None);
+        assert_eq!(super::yaml::set(compact, "servers.port", &json!(2)), None);
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn yaml_missing_keys_are_added_after_a_sequence() {
+        let content = "db:\n  hosts:\n    - a\n    - b\nlog: info\n";
+
+        let updated = super::yaml::set(content, "db.pool", &json!(4)).unwrap();
+
+        assert_eq!(
+            updated,
+            "db:\n  hosts:\n    - a\n    - b\n  pool: 4\nlog: info\n"
+        );
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn yaml_multi_line_strings_are_not_edited_in_place() {
+        let content = "server:\n  motd: hi # greeting\n  port: 1\n";
+
+        assert_eq!(
+            super::yaml::set(content, "server.motd", &json!("line1\nline2")),
+            None
+        );
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn yaml_multi_line_strings_are_written_readably() {
+        let path = std::env::temp_dir().join(format!("profig-edit-{}.yaml", std::process::id()));
+        let path = path.to_string_lossy().into_owned();
+        std::fs::write(&path, "server:\n  motd: hi # greeting\n  port: 1\n").unwrap();
+
+        super::set(&path, "server.motd", "line1\nline2", &[]).unwrap();
+
+        let written = std::fs::read_to_string(&path).unwrap();
+        let doc: Value = serde_yaml::from_str(&written).unwrap();
+        assert_eq!(
+            doc,
+            json!({ "server": { "motd": "line1\nline2", "port": 1 } })
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,88 @@
+use profig_commons::error::ProfigError;
+use serde_json::Value;
+use std::sync::{Arc, OnceLock, RwLock};
+
+/// A config file format that profig can load and write.
+///
+/// Built-in formats are registered automatically when their feature is enabled;
+/// custom formats are added with [`register`].
+pub trait ConfigFormat: Send + Sync {
+    /// Name used in `#[profig(custom_format = "...")]` (or `format` for built-in formats).
+    fn name(&self) -> &'static str;
+
+    /// File extensions (lowercase, without the dot) handled by this format.
+    fn extensions(&self) -> &'static [&'static str];
+
+    /// Parses file contents into a JSON value.
+    fn parse(&self, content: &str) -> Result<Value, ProfigError>;
+
+    /// Serializes a JSON value into file contents.
+    fn serialize(&self, value: &Value) -> Result<String, ProfigError>;
+
+    /// Whether parsed values are all strings, as in INI or `.properties` files.
+    /// Such files are coerced to the field types on load (see [`crate::coerce`]).
+    fn string_values(&self) -> bool {
+        false
+    }
+}
+
+type Registry = RwLock<Vec<Arc<dyn ConfigFormat>>>;
+
+fn registry() -> &'static Registry {
+    static REGISTRY: OnceLock<Registry> = OnceLock::new();
+
+    REGISTRY.get_or_init(|| {
+        let formats: Vec<Arc<dyn ConfigFormat>> = vec![
+            #[cfg(feature = "toml")]
+            Arc::new(crate::loader::toml::TomlFormat),
+            #[cfg(feature = "json")]
+            Arc::new(crate::loader::json::JsonFormat),
+            #[cfg(feature = "yaml")]
+            Arc::new(crate::loader::yaml::YamlFormat),
+        ];
+
+        RwLock::new(formats)
+    })
+}
+
+/// Registers a custom format. A format registered later takes precedence over
+/// an earlier one with the same name or extension.
+pub fn register<F: ConfigFormat + 'static>(format: F) {
+    registry()
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(0, Arc::new(format));
+}
+
+/// Looks up a format by name, falling back to its extensions (so `"yml"` finds YAML).
+pub fn find_by_name(name: &str) -> Option<Arc<dyn ConfigFormat>> {
+    let name = name.trim().to_ascii_lowercase();
+    let formats = registry().read().unwrap_or_else(|e| e.into_inner());
+
+    formats
+        .iter()
+        .find(|f| f.name() == name)
+        .or_else(|| {
+            formats
+                .iter()
+                .find(|f| f.extensions().contains(&name.as_str()))
+        })
+        .cloned()
+}
+
+/// Looks up a format by file extension (case-insensitive).
+pub fn find_by_extension(ext: &str) -> Option<Arc<dyn ConfigFormat>> {
+    let ext = ext.to_ascii_lowercase();
+    let formats = registry().read().unwrap_or_else(|e| e.into_inner());
+
+    formats
+        .iter()
+        .find(|f| f.extensions().contains(&ext.as_str()))
+        .cloned()
+}
+
+/// Names of all registered formats.
+pub fn names() -> Vec<&'static str> {
+    let formats = registry().read().unwrap_or_else(|e| e.into_inner());
+    formats.iter().map(|f| f.name()).collect()
+}
@@ -3,6 +3,93 @@ use profig_commons::{
     types::{FieldSchema, FieldType},
 };
 
+use crate::validator::bound_text;
+
+/// Markdown list items describing bounds, string constraints and cross-field rules of a field.
+fn rule_lines(f: &FieldSchema) -> Vec<String> {
+    let meta = &f.metadata;
+    let mut lines = vec![];
+
+    if let Some(min) = meta.min {
+        lines.push(format!("- Minimum: `{}`", bound_text(min, &f.ty)));
+    }
+    if let Some(max) = meta.max {
+        lines.push(format!("- Maximum: `{}`", bound_text(max, &f.ty)));
+    }
+
+    if meta.trim {
+        lines.push("- Leading and trailing whitespace is trimmed".to_string());
+    }
+    if meta.lowercase {
+        lines.push("- Converted to lowercase".to_string());
+    } else if meta.uppercase {
+        lines.push("- Converted to uppercase".to_string());
+    }
+    if meta.non_empty {
+        lines.push("- Must not be empty".to_string());
+    }
+    match (meta.min_len, meta.max_len) {
+        (Some(min), Some(max)) => lines.push(format!("- Length: {} to {} characters", min, max)),
+        (Some(min), None) => lines.push(format!("- Length: at least {} characters", min)),
+        (None, Some(max)) => lines.push(format!("- Length: at most {} characters", max)),
+        (None, None) => {}
+    }
+    if meta.ascii_only {
+        lines.push("- ASCII characters only".to_string());
+    }
+    if meta.create_dir {
+        lines.push("- Directory is created if missing".to_string());
+    }
+    if meta.is_file {
+        lines.push("- Must be an existing file".to_string());
+    } else if meta.is_dir {
+        lines.push("- Must be an existing directory".to_string());
+    } else if meta.must_exist {
+        lines.push("- Must exist".to_string());
+    }
+    if meta.readable {
+        lines.push("- Must be readable".to_string());
+    }
+    if meta.writable {
+        lines.push("- Must be writable".to_string());
+    }
+    if matches!(f.ty, FieldType::Path) {
+        lines.push("- Relative paths are resolved against the config file's directory".to_string());
+    }
+    if let Some(format) = &meta.format {
+        lines.push(format!(
+            "- Must be a valid {}",
+            crate::validator::format_description(format)
+        ));
+    }
+
+    for c in &meta.required_if {
+        lines.push(match &c.equals {
+            Some(v) => format!("- Required when `{}` is `{}`", c.field, v),
+            None => format!("- Required when `{}` is set", c.field),
+        });
+    }
+    if !meta.conflicts_with.is_empty() {
+        let names: Vec<String> = meta
+            .conflicts_with
+            .iter()
+            .map(|n| format!("`{}`", n))
+            .collect();
+        lines.push(format!(
+            "- Cannot be set together with {}",
+            names.join(", ")
+        ));
+    }
+    if let Some(other) = &meta.gt {
+        lines.push(format!("- Must be greater than `{}`", other));
+    }
+    if let Some(other) = &meta.lt {
+        lines.push(format!("- Must be less than `{}`", other));
+    }
+
+    lines
+}
+
 pub fn generate_doc(
     path: &str,
     schema: &[FieldSchema],
@@ -17,10 +104,37 @@ pub fn generate_doc(
     for f in schema {
         let meta = &f.metadata;
 
-        if let Some(d) = &meta.doc {
+        let rules = rule_lines(f);
+
+        if meta.doc.is_some()
+            || meta.deprecated.is_some()
+            || !meta.aliases.is_empty()
+            || !rules.is_empty()
+        {
             // let content = format!("{}\n{}", content, d.as_str()).as_str();
-            let heading = format!("## Field: *{}* - `{:?}`", &f.name, &f.ty);
-            let body = format!("{}\n{}", heading, d);
+            let ty = match &f.ty {
+                FieldType::Int(kind) => kind.name().to_string(),
+                other => format!("{:?}", other),
+            };
+            let mut heading = format!("## Field: *{}* - `{}`", &f.name, ty);
+            if meta.deprecated.is_some() {
+                heading.push_str(" *(deprecated)*");
+            }
+
+            let mut body = heading;
+            if let Some(d) = &meta.doc {
+                body = format!("{}\n{}", body, d);
+            }
+            if let Some(msg) = &meta.deprecated {
+                body = format!("{}\n\n> **Deprecated:** {}", body, msg);
+            }
+            if !meta.aliases.is_empty() {
+                let names: Vec<String> = meta.aliases.iter().map(|a| format!("`{}`", a)).collect();
+                body = format!("{}\n\nDeprecated names: {}", body, names.join(", "));
+            }
+            if !rules.is_empty() {
+                body = format!("{}\n\n{}", body, rules.join("\n"));
+            }
             content = content.clone().to_owned() + "\n\n" + body.as_str();
         }
     }
@@ -31,6 +145,23 @@ pub fn generate_doc(
     Ok(())
 }
 
+pub fn json_schema(
+    path: &str,
+    schema: &[FieldSchema],
+    name: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let doc = crate::json_schema::export(schema, name);
+    let content = serde_json::to_string_pretty(&doc).map_err(|e| ProfigError::Parse {
+        format: "json",
+        error: e.to_string(),
+    })?;
+
+    std::fs::write(path, content).map_err(ProfigError::from)?;
+    println!("\nJSON Schema created at {}.", path);
+
+    Ok(())
+}
+
 pub fn sample_conf(path: &str, schema: &[FieldSchema]) -> Result<(), Box<dyn std::error::Error>> {
     let mut map = serde_json::Map::new();
 
@@ -38,7 +169,9 @@ pub fn sample_conf(path: &str, schema: &[FieldSchema]) -> Result<(), Box<dyn std
         let meta = &f.metadata;
 
         let value = match &f.ty {
-            FieldType::Str => {
+            // Secrets never end up in samples, not even their defaults
+            _ if meta.secret => serde_json::Value::String(crate::secret::REDACTED.to_string()),
+            FieldType::Str | FieldType::Path => {
                 if let Some(def) = &meta.default {
                     serde_json::Value::String(def.clone())
                 } else if let Some(r) = &meta.regex {
@@ -47,7 +180,7 @@ pub fn sample_conf(path: &str, schema: &[FieldSchema]) -> Result<(), Box<dyn std
                         serde_json::Value::String("REQUIRED".to_string())
                 }
             }
-            FieldType::Int => {
+            FieldType::Int(_) => {
                 if let Some(def) = &meta.default {
                     serde_json::Value::Number(def.parse().unwrap_or(serde_json::Number::from(0)))
                 } else if let Some(min) = &meta.min {
@@ -77,44 +210,23 @@ pub fn sample_conf(path: &str, schema: &[FieldSchema]) -> Result<(), Box<dyn std
                     serde_json::Value::Bool(false)
                 }
             }
+            FieldType::Duration | FieldType::Bytes => {
+                if let Some(def) = &meta.default {
+                    serde_json::Value::String(def.clone())
+                } else {
+                    serde_json::Value::String(bound_text(meta.min.unwrap_or(0.0), &f.ty))
+                }
+            }
         };
 
         map.insert(f.name.clone(), value);
     }
 
-    let _val = serde_json::Value::Object(map);
+    let val = serde_json::Value::Object(map);
 
-    // Auto-detect config file type
-    let ext = std::path::Path::new(path)
-        .extension()
-        .and_then(|s| s.to_str())
-        .unwrap_or("")
-        .to_ascii_lowercase();
+    // Format is picked from the extension through the shared registry
+    crate::loader::save_sample(path, &val)?;
+    println!("\nSample config created at {}.", path);
 
-    #[cfg(feature = "json")]
-    if ext == "json" {
-        crate::loader::json::save_sample(path, &_val)?;
-        println!("\nSample config created at {}.", path);
-        return Ok(());
-    }
-
-    #[cfg(feature = "toml")]
-    if ext == "toml" {
-        crate::loader::toml::save_sample(path, &_val)?;
-        println!("\nSample config created at {}.", path);
-        return Ok(());
-    }
-
-    #[cfg(feature = "yaml")]
-    if ext == "yaml" || ext == "yml" {
-        crate::loader::yaml::save_sample(path, &_val)?;
-        println!("\nSample config created at {}.", path);
-        return Ok(());
-    }
-
-    Err(Box::new(ProfigError::InvalidFormat(format!(
-        "Unsupported or missing file extension: '{}'",
-        ext
-    ))))
-    // Ok(())
+    Ok(())
 }
@@ -0,0 +1,353 @@
+use profig_commons::error::ProfigError;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+use crate::loader::format_for_path;
+use crate::merge::deep_merge;
+
+/// Top-level key listing files to include.
+pub const INCLUDE_KEY: &str = "include";
+
+/// Tag used for inline includes, e.g. YAML `key: !include other.yaml`.
+pub const INCLUDE_TAG: &str = "!include";
+
+fn chain_names(chain: &[PathBuf]) -> Vec<String> {
+    chain.iter().map(|p| p.display().to_string()).collect()
+}
+
+fn include_error(chain: &[PathBuf], error: impl ToString) -> ProfigError {
+    ProfigError::Include {
+        chain: chain_names(chain),
+        error: error.to_string(),
+    }
+}
+
+/// Matches a file name against a pattern with `*` and `?` wildcards.
+fn wildcard_match(pattern: &[char], name: &[char]) -> bool {
+    match (pattern.first(), name.first()) {
+        (None, None) => true,
+        (Some('*'), _) => {
+            wildcard_match(&pattern[1..], name)
+                || (!name.is_empty() && wildcard_match(pattern, &name[1..]))
+        }
+        (Some('?'), Some(_)) => wildcard_match(&pattern[1..], &name[1..]),
+        (Some(p), Some(n)) if p == n => wildcard_match(&pattern[1..], &name[1..]),
+        _ => false,
+    }
+}
+
+/// Expands an include pattern relative to `base_dir`. Wildcards are only
+/// supported in the file name; matches are returned in sorted order.
+fn expand(base_dir: &Path, pattern: &str) -> Result<Vec<PathBuf>, String> {
+    let full = base_dir.join(pattern);
+    let file_name = full
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or_else(|| format!("Invalid include '{}'", pattern))?;
+
+    if !file_name.contains(['*', '?']) {
+        return if full.is_file() {
+            Ok(vec![full])
+        } else {
+            Err(format!("Included file '{}' not found", full.display()))
+        };
+    }
+
+    let dir = full.parent().unwrap_or(base_dir);
+    let pattern: Vec<char> = file_name.chars().collect();
+    let entries = std::fs::read_dir(dir)
+        .map_err(|e| format!("Cannot read directory '{}': {}", dir.display(), e))?;
+
+    let mut matches: Vec<PathBuf> = entries
+        .filter_map(Result::ok)
+        .map(|e| e.path())
+        .filter(|p| p.is_file())
+        .filter(|p| {
+            p.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| wildcard_match(&pattern, &n.chars().collect::<Vec<_>>()))
+        })
+        .collect();
+    matches.sort();
+
+    Ok(matches)
+}
+
+/// Replaces every `{"!include": "file"}` node with the contents of that file.
+fn resolve_tags(
+    value: &mut Value,
+    base_dir: &Path,
+    allowed: &[&str],
+    chain: &mut Vec<PathBuf>,
+) -> Result<(), ProfigError> {
+    match value {
+        Value::Object(map) if map.len() == 1 && map.contains_key(INCLUDE_TAG) => {
+            let target = match &map[INCLUDE_TAG] {
+                Value::String(s) => s.clone(),
+                other => {
+                    return Err(include_error(
+                        chain,
+                        format!("'{}' expects a file path, got {}", INCLUDE_TAG, other),
+                    ))
+                }
+            };
+            let files = expand(base_dir, &target).map_err(|e| include_error(chain, e))?;
+
+            let mut merged = Value::Null;
+            for file in files {
+                deep_merge(&mut merged, load_file(&file, allowed, chain)?);
+            }
+            *value = merged;
+        }
+        Value::Object(map) => {
+            for v in map.values_mut() {
+                resolve_tags(v, base_dir, allowed, chain)?;
+            }
+        }
+        Value::Array(items) => {
+            for v in items {
+                resolve_tags(v, base_dir, allowed, chain)?;
+            }
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+fn load_file(
+    path: &Path,
+    allowed: &[&str],
+    chain: &mut Vec<PathBuf>,
+) -> Result<Value, ProfigError> {
+    let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+
+    if let Some(pos) = chain.iter().position(|p| p == &canonical) {
+        let mut cycle = chain[pos..].to_vec();
+        cycle.push(canonical);
+        return Err(include_error(&cycle, "include cycle detected"));
+    }
+    chain.push(canonical);
+
+    let path_str = path.to_string_lossy();
+    let fmt = format_for_path(&path_str, allowed).map_err(|e| wrap(chain, e))?;
+    let content = std::fs::read_to_string(path).map_err(|e| wrap(chain, ProfigError::from(e)))?;
+    let value = fmt.parse(&content).map_err(|e| wrap(chain, e))?;
+    let merged = resolve_includes(value, path, allowed, chain)?;
+
+    chain.pop();
+    Ok(merged)
+}
+
+/// Resolves the includes of `value`, the parsed contents of `path`, which must
+/// be the last entry of `chain`.
+fn resolve_includes(
+    mut value: Value,
+    path: &Path,
+    allowed: &[&str],
+    chain: &mut Vec<PathBuf>,
+) -> Result<Value, ProfigError> {
+    let base_dir = path.parent().unwrap_or(Path::new("")).to_path_buf();
+    let includes = match value.as_object_mut().and_then(|m| m.remove(INCLUDE_KEY)) {
+        None => vec![],
+        Some(Value::String(s)) => vec![s],
+        Some(Value::Array(items)) => items
+            .into_iter()
+            .map(|i| match i {
+                Value::String(s) => Ok(s),
+                other => Err(include_error(
+                    chain,
+                    format!("'{}' entries must be strings, got {}", INCLUDE_KEY, other),
+                )),
+            })
+            .collect::<Result<_, _>>()?,
+        Some(other) => {
+            return Err(include_error(
+                chain,
+                format!(
+                    "'{}' must be a string or a list of strings, got {}",
+                    INCLUDE_KEY, other
+                ),
+            ))
+        }
+    };
+
+    resolve_tags(&mut value, &base_dir, allowed, chain)?;
+
+    // Included files are merged in order, then the including file on top
+    let mut merged = Value::Object(serde_json::Map::new());
+    for pattern in includes {
+        let files = expand(&base_dir, &pattern).map_err(|e| include_error(chain, e))?;
+        for file in files {
+            deep_merge(&mut merged, load_file(&file, allowed, chain)?);
+        }
+    }
+    deep_merge(&mut merged, value);
+
+    Ok(merged)
+}
+
+/// Attaches the include chain to errors from included files; errors in the
+/// top-level file are reported unchanged.
+fn wrap(chain: &[PathBuf], err: ProfigError) -> ProfigError {
+    if chain.len() > 1 {
+        include_error(chain, err)
+    } else {
+        err
+    }
+}
+
+/// Loads `path` and resolves its `include = [...]` list and `!include` tags,
+/// relative to the including file.
+pub fn load(path: &str, allowed: &[&str]) -> Result<Value, ProfigError> {
+    load_file(Path::new(path), allowed, &mut vec![])
+}
+
+/// Whether a parsed (unresolved) document includes other files, through the
+/// top-level `include` key or `!include` tags.
+pub fn uses_includes(doc: &Value) -> bool {
+    fn has_tags(v: &Value) -> bool {
+        match v {
+            Value::Object(map) => map.contains_key(INCLUDE_TAG) || map.values().any(has_tags),
+            Value::Array(items) => items.iter().any(has_tags),
+            _ => false,
+        }
+    }
+
+    doc.get(INCLUDE_KEY).is_some() || has_tags(doc)
+}
+
+/// Like `load`, for a document that was already read from `path` (e.g. an
+/// edited copy that hasn't been written yet).
+pub fn resolve(doc: Value, path: &str, allowed: &[&str]) -> Result<Value, ProfigError> {
+    let path = Path::new(path);
+    let mut chain = vec![path.canonicalize().unwrap_or_else(|_| path.to_path_buf())];
+
+    resolve_includes(doc, path, allowed, &mut chain)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::{self, ConfigFormat};
+
+    /// Plain JSON under its own extension, so the tests don't depend on format features.
+    struct TestJson;
+
+    impl ConfigFormat for TestJson {
+        fn name(&self) -> &'static str {
+            "testjson"
+        }
+
+        fn extensions(&self) -> &'static [&'static str] {
+            &["testjson"]
+        }
+
+        fn parse(&self, content: &str) -> Result<Value, ProfigError> {
+            serde_json::from_str(content).map_err(|e| ProfigError::Parse {
+                format: "json",
+                error: e.to_string(),
+            })
+        }
+
+        fn serialize(&self, value: &Value) -> Result<String, ProfigError> {
+            Ok(value.to_string())
+        }
+    }
+
+    /// Writes `files` into a fresh directory and returns it.
+    fn write_files(test: &str, files: &[(&str, &str)]) -> PathBuf {
+        format::register(TestJson);
+
+        let dir =
+            std::env::temp_dir().join(format!("profig-include-{}-{}", std::process::id(), test));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        for (name, content) in files {
+            std::fs::write(dir.join(name), content).unwrap();
+        }
+        dir
+    }
+
+    fn load_in(dir: &Path, file: &str) -> Result<Value, ProfigError> {
+        load(&dir.join(file).to_string_lossy(), &["testjson"])
+    }
+
+    #[test]
+    fn includes_are_merged_under_the_including_file() {
+        let dir = write_files(
+            "merge",
+            &[
+                (
+                    "main.testjson",
+                    r#"{"include": "base.testjson", "port": 9000}"#,
+                ),
+                ("base.testjson", r#"{"port": 8000, "host": "localhost"}"#),
+            ],
+        );
+
+        let value = load_in(&dir, "main.testjson").unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({"port": 9000, "host": "localhost"})
+        );
+    }
+
+    #[test]
+    fn detects_include_cycles() {
+        let dir = write_files(
+            "cycle",
+            &[
+                ("a.testjson", r#"{"include": "b.testjson"}"#),
+                ("b.testjson", r#"{"include": ["a.testjson"]}"#),
+            ],
+        );
+
+        match load_in(&dir, "a.testjson") {
+            Err(ProfigError::Include { chain, error }) => {
+                assert_eq!(error, "include cycle detected");
+                let names: Vec<_> = chain
+                    .iter()
+                    .map(|p| {
+                        Path::new(p)
+                            .file_name()
+                            .unwrap()
+                            .to_string_lossy()
+                            .into_owned()
+                    })
+                    .collect();
+                assert_eq!(names, ["a.testjson", "b.testjson", "a.testjson"]);
+            }
+            other => panic!("expected an include cycle error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn detects_a_file_including_itself() {
+        let dir = write_files("self", &[("a.testjson", r#"{"include": "a.testjson"}"#)]);
+
+        assert!(matches!(
+            load_in(&dir, "a.testjson"),
+            Err(ProfigError::Include { error, .. }) if error == "include cycle detected"
+        ));
+    }
+
+    #[test]
+    fn shared_includes_are_not_a_cycle() {
+        let dir = write_files(
+            "diamond",
+            &[
+                (
+                    "main.testjson",
+                    r#"{"include": ["b.testjson", "c.testjson"]}"#,
+                ),
+                ("b.testjson", r#"{"include": "common.testjson", "b": 1}"#),
+                ("c.testjson", r#"{"include": "common.testjson", "c": 2}"#),
+                ("common.testjson", r#"{"common": true}"#),
+            ],
+        );
+
+        let value = load_in(&dir, "main.testjson").unwrap();
+        assert_eq!(value, serde_json::json!({"b": 1, "c": 2, "common": true}));
+    }
+}
@@ -0,0 +1,375 @@
+use profig_commons::error::ProfigError;
+use profig_commons::types::{FieldSchema, FieldType};
+use serde_json::Value;
+use std::collections::HashMap;
+
+use crate::secret::shown;
+
+fn lookup<'a>(root: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.').try_fold(root, |v, part| match v {
+        Value::Object(map) => map.get(part),
+        Value::Array(items) => part.parse::<usize>().ok().and_then(|i| items.get(i)),
+        _ => None,
+    })
+}
+
+fn lookup_mut<'a>(root: &'a mut Value, path: &str) -> Option<&'a mut Value> {
+    path.split('.').try_fold(root, |v, part| match v {
+        Value::Object(map) => map.get_mut(part),
+        Value::Array(items) => part.parse::<usize>().ok().and_then(|i| items.get_mut(i)),
+        _ => None,
+    })
+}
+
+fn has_refs(s: &str) -> bool {
+    s.contains("${")
+}
+
+fn display(v: &Value) -> String {
+    match v {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// A piece of a string value: literal text or a `${...}` reference.
+enum Part<'a> {
+    Text(&'a str),
+    Ref {
+        name: &'a str,
+        fallback: Option<&'a str>,
+    },
+}
+
+fn split_parts<'a>(s: &'a str, at: &str) -> Result<Vec<Part<'a>>, ProfigError> {
+    let mut parts = vec![];
+    let mut rest = s;
+
+    while let Some(start) = rest.find('$') {
+        let (before, after) = rest.split_at(start);
+        if !before.is_empty() {
+            parts.push(Part::Text(before));
+        }
+
+        if let Some(tail) = after.strip_prefix("$${") {
+            // `$${` is an escaped literal `${`
+            parts.push(Part::Text("${"));
+            rest = tail;
+        } else if let Some(tail) = after.strip_prefix("${") {
+            let end = tail.find('}').ok_or_else(|| {
+                ProfigError::Validation(format!("Unterminated '${{' in field '{}'", at))
+            })?;
+            let expr = &tail[..end];
+            let (name, fallback) = match expr.split_once(":-") {
+                Some((n, f)) => (n.trim(), Some(f)),
+                None => (expr.trim(), None),
+            };
+            parts.push(Part::Ref { name, fallback });
+            rest = &tail[end + 1..];
+        } else {
+            parts.push(Part::Text("$"));
+            rest = &after[1..];
+        }
+    }
+
+    if !rest.is_empty() {
+        parts.push(Part::Text(rest));
+    }
+
+    Ok(parts)
+}
+
+struct Resolver<'a> {
+    root: &'a Value,
+    resolved: HashMap<String, Value>,
+    stack: Vec<String>,
+}
+
+impl Resolver<'_> {
+    /// Resolved value of the config entry at `path`, or `None` if there is none.
+    fn resolve_path(&mut self, path: &str) -> Result<Option<Value>, ProfigError> {
+        if let Some(v) = self.resolved.get(path) {
+            return Ok(Some(v.clone()));
+        }
+
+        if let Some(pos) = self.stack.iter().position(|p| p == path) {
+            let mut cycle: Vec<&str> = self.stack[pos..].iter().map(String::as_str).collect();
+            cycle.push(path);
+            return Err(ProfigError::Validation(format!(
+                "Reference cycle: {}",
+                cycle.join(" -> ")
+            )));
+        }
+
+        let value = match lookup(self.root, path) {
+            Some(Value::String(s)) if has_refs(s) => {
+                let s = s.clone();
+                self.stack.push(path.to_string());
+                let v = self.resolve_string(&s, path)?;
+                self.stack.pop();
+                v
+            }
+            Some(v) => v.clone(),
+            None => return Ok(None),
+        };
+
+        self.resolved.insert(path.to_string(), value.clone());
+        Ok(Some(value))
+    }
+
+    fn resolve_ref(
+        &mut self,
+        name: &str,
+        fallback: Option<&str>,
+        at: &str,
+    ) -> Result<Value, ProfigError> {
+        if let Some(v) = self.resolve_path(name)? {
+            return Ok(v);
+        }
+        if let Ok(v) = std::env::var(name) {
+            return Ok(Value::String(v));
+        }
+        if let Some(f) = fallback {
+            return Ok(Value::String(f.to_string()));
+        }
+
+        Err(ProfigError::Validation(format!(
+            "Unresolved reference '${{{}}}' in field '{}': no such config value or environment variable",
+            name, at
+        )))
+    }
+
+    fn resolve_string(&mut self, s: &str, at: &str) -> Result<Value, ProfigError> {
+        let parts = split_parts(s, at)?;
+
+        // A value that is exactly one reference keeps the referenced type
+        if let [Part::Ref { name, fallback }] = parts.as_slice() {
+            return self.resolve_ref(name, *fallback, at);
+        }
+
+        let mut out = String::new();
+        for part in parts {
+            match part {
+                Part::Text(t) => out.push_str(t),
+                Part::Ref { name, fallback } => {
+                    out.push_str(&display(&self.resolve_ref(name, fallback, at)?))
+                }
+            }
+        }
+
+        Ok(Value::String(out))
+    }
+}
+
+/// Collects the dotted paths of string values in `value` that contain `${`.
+pub fn string_paths(value: &Value, prefix: &str, out: &mut Vec<String>) {
+    let join = |k: &str| {
+        if prefix.is_empty() {
+            k.to_string()
+        } else {
+            format!("{}.{}", prefix, k)
+        }
+    };
+
+    match value {
+        Value::String(s) if has_refs(s) => out.push(prefix.to_string()),
+        Value::Object(map) => {
+            for (k, v) in map {
+                string_paths(v, &join(k), out);
+            }
+        }
+        Value::Array(items) => {
+            for (i, v) in items.iter().enumerate() {
+                string_paths(v, &join(&i.to_string()), out);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Puts the `${...}` references of `raw` (a config file as written) back into `config`
+/// wherever the value they resolved to in `previous` (the same file as loaded) is
+/// unchanged, so saving a config doesn't write out what they expanded to.
+pub fn keep_references(config: &mut Value, previous: &Value, raw: &Value) {
+    let mut paths = vec![];
+    string_paths(raw, "", &mut paths);
+
+    for path in paths {
+        let unchanged =
+            lookup(previous, &path).is_some_and(|old| lookup(config, &path) == Some(old));
+        if let (true, Some(slot), Some(written)) =
+            (unchanged, lookup_mut(config, &path), lookup(raw, &path))
+        {
+            *slot = written.clone();
+        }
+    }
+}
+
+fn coerce(value: Value, f: &FieldSchema) -> Result<Value, ProfigError> {
+    let (ty, name) = (&f.ty, &f.name);
+    let s = match (ty, value) {
+        (FieldType::Str | FieldType::Path, Value::String(s)) => return Ok(Value::String(s)),
+        (FieldType::Str | FieldType::Path, other) => return Ok(Value::String(other.to_string())),
+        // Parsed (and unit-checked) by the validator
+        (FieldType::Duration | FieldType::Bytes, v) => return Ok(v),
+        (_, Value::String(s)) => s,
+        (_, other) => return Ok(other),
+    };
+
+    let expected = match ty {
+        FieldType::Int(_) => "an integer",
+        FieldType::Float => "a number",
+        _ => "a boolean",
+    };
+
+    crate::coerce::parse(&s, ty).ok_or_else(|| {
+        ProfigError::Validation(format!(
+            "Interpolated value '{}' for field '{}' is not {}",
+            shown(&s, &f.metadata),
+            name,
+            expected
+        ))
+    })
+}
+
+/// Resolves `${ENV_VAR}`, `${ENV_VAR:-fallback}` and `${other.field}` references
+/// inside string values. Config values take precedence over environment variables;
+/// `$${` produces a literal `${`.
+///
+/// Interpolated values of `Int`, `Float` and `Bool` fields are converted to their type.
+pub fn interpolate(config: &mut Value, schema: &[FieldSchema]) -> Result<(), ProfigError> {
+    let mut paths = vec![];
+    string_paths(config, "", &mut paths);
+    if paths.is_empty() {
+        return Ok(());
+    }
+
+    let snapshot = config.clone();
+    let mut resolver = Resolver {
+        root: &snapshot,
+        resolved: HashMap::new(),
+        stack: vec![],
+    };
+
+    let mut results = vec![];
+    for path in &paths {
+        if let Some(v) = resolver.resolve_path(path)? {
+            results.push((path, v));
+        }
+    }
+
+    for (path, mut v) in results {
+        if let Some(f) = schema.iter().find(|f| &f.name == path) {
+            v = coerce(v, f)?;
+        }
+        if let Some(slot) = lookup_mut(config, path) {
+            *slot = v;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::field;
+    use profig_commons::types::IntKind;
+    use serde_json::json;
+
+    fn error_of(result: Result<(), ProfigError>) -> String {
+        match result {
+            Err(e) => e.to_string(),
+            Ok(()) => panic!("expected an error"),
+        }
+    }
+
+    #[test]
+    fn resolves_config_references_and_escapes() {
+        let mut config = json!({
+            "host": "example.com",
+            "url": "https://${host}/api",
+            "server": { "name": "${host}" },
+            "literal": "$${host}",
+        });
+        interpolate(&mut config, &[]).unwrap();
+
+        assert_eq!(config["url"], "https://example.com/api");
+        assert_eq!(config["server"]["name"], "example.com");
+        assert_eq!(config["literal"], "${host}");
+    }
+
+    #[test]
+    fn resolves_environment_variables_and_fallbacks() {
+        std::env::set_var("PROFIG_TEST_INTERPOLATE_HOME", "/home/app");
+        let mut config = json!({
+            "dir": "${PROFIG_TEST_INTERPOLATE_HOME}/data",
+            "level": "${PROFIG_TEST_INTERPOLATE_UNSET:-info}",
+        });
+        interpolate(&mut config, &[]).unwrap();
+
+        assert_eq!(config["dir"], "/home/app/data");
+        assert_eq!(config["level"], "info");
+    }
+
+    #[test]
+    fn whole_references_keep_the_referenced_type() {
+        let schema = [field("workers", FieldType::Int(IntKind::U32))];
+        let mut config = json!({ "threads": 8, "workers": "${threads}" });
+        interpolate(&mut config, &schema).unwrap();
+
+        assert_eq!(config["workers"], 8);
+    }
+
+    #[test]
+    fn interpolated_strings_are_converted_to_the_field_type() {
+        std::env::set_var("PROFIG_TEST_INTERPOLATE_PORT", "8080");
+        let schema = [field("port", FieldType::Int(IntKind::U16))];
+        let mut config = json!({ "port": "${PROFIG_TEST_INTERPOLATE_PORT}" });
+        interpolate(&mut config, &schema).unwrap();
+
+        assert_eq!(config["port"], 8080);
+    }
+
+    #[test]
+    fn detects_reference_cycles() {
+        let mut config = json!({ "a": "${b}", "b": "x-${c}", "c": "${a}" });
+
+        let err = error_of(interpolate(&mut config, &[]));
+        assert!(err.contains("Reference cycle: a -> b -> c -> a"), "{}", err);
+    }
+
+    #[test]
+    fn detects_a_value_referencing_itself() {
+        let mut config = json!({ "a": "prefix-${a}" });
+
+        let err = error_of(interpolate(&mut config, &[]));
+        assert!(err.contains("Reference cycle: a -> a"), "{}", err);
+    }
+
+    #[test]
+    fn reports_unresolved_references() {
+        let mut config = json!({ "a": "${PROFIG_TEST_INTERPOLATE_MISSING}" });
+
+        let err = error_of(interpolate(&mut config, &[]));
+        assert!(err.contains("Unresolved reference"), "{}", err);
+        assert!(err.contains("field 'a'"), "{}", err);
+    }
+
+    #[test]
+    fn keep_references_restores_only_unchanged_values() {
+        let raw =
+            json!({ "url": "https://${host}/api", "dir": "${HOME}/data", "literal": "$${x}" });
+        let previous =
+            json!({ "url": "https://a/api", "dir": "/home/app/data", "literal": "${x}" });
+        let mut config =
+            json!({ "url": "https://a/api", "dir": "/srv/data", "literal": "${x}", "port": 1 });
+
+        keep_references(&mut config, &previous, &raw);
+
+        assert_eq!(
+            config,
+            json!({ "url": "https://${host}/api", "dir": "/srv/data", "literal": "$${x}", "port": 1 })
+        );
+    }
+}
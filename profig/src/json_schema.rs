@@ -0,0 +1,345 @@
+use profig_commons::error::ProfigError;
+use profig_commons::types::{
+    Condition, FieldSchema, FieldType, IntKind, MetaField, STRING_FORMATS,
+};
+use serde_json::{json, Map, Value};
+
+use crate::units;
+use crate::validator::bound_text;
+
+fn type_name(ty: &FieldType) -> &'static str {
+    match ty {
+        FieldType::Int(_) => "integer",
+        FieldType::Float => "number",
+        FieldType::Str => "string",
+        FieldType::Bool => "boolean",
+        FieldType::Duration | FieldType::Bytes | FieldType::Path => "string",
+    }
+}
+
+fn typed_default(def: &str, ty: &FieldType) -> Value {
+    let parsed = match ty {
+        FieldType::Int(_) | FieldType::Float | FieldType::Bool => serde_json::from_str(def).ok(),
+        FieldType::Str | FieldType::Duration | FieldType::Bytes | FieldType::Path => None,
+    };
+    parsed.unwrap_or_else(|| Value::String(def.to_string()))
+}
+
+fn strings(v: Option<&Value>) -> Vec<String> {
+    v.and_then(Value::as_array)
+        .map(|a| {
+            a.iter()
+                .filter_map(Value::as_str)
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn int_value(v: i128) -> Value {
+    match u64::try_from(v) {
+        Ok(u) => json!(u),
+        Err(_) => json!(v as i64),
+    }
+}
+
+fn bound(v: f64, ty: &FieldType) -> Value {
+    match ty {
+        FieldType::Duration | FieldType::Bytes => json!(bound_text(v, ty)),
+        FieldType::Int(_) if v.fract() == 0.0 && v >= 0.0 => json!(v as u64),
+        FieldType::Int(_) if v.fract() == 0.0 => json!(v as i64),
+        _ => json!(v),
+    }
+}
+
+/// Exports a schema as a JSON Schema document.
+///
+/// Fields without a default that are not `Option<T>` are listed as `required`.
+pub fn export(schema: &[FieldSchema], title: &str) -> Value {
+    let mut properties = Map::new();
+    let mut required = vec![];
+
+    for f in schema {
+        let meta = &f.metadata;
+        let mut prop = Map::new();
+        prop.insert("type".to_string(), json!(type_name(&f.ty)));
+        let unit_type = match f.ty {
+            FieldType::Duration => Some("duration"),
+            FieldType::Bytes => Some("bytes"),
+            _ => None,
+        };
+        if let Some(unit) = unit_type {
+            prop.insert("x-type".to_string(), json!(unit));
+        }
+        if matches!(f.ty, FieldType::Path) {
+            prop.insert("x-type".to_string(), json!("path"));
+            let checks: Vec<&str> = [
+                ("must_exist", meta.must_exist),
+                ("is_file", meta.is_file),
+                ("is_dir", meta.is_dir),
+                ("readable", meta.readable),
+                ("writable", meta.writable),
+                ("create_dir", meta.create_dir),
+            ]
+            .into_iter()
+            .filter_map(|(name, set)| set.then_some(name))
+            .collect();
+            if !checks.is_empty() {
+                prop.insert("x-path-checks".to_string(), json!(checks));
+            }
+        }
+
+        if let Some(doc) = &meta.doc {
+            prop.insert("description".to_string(), json!(doc));
+        }
+        if meta.secret {
+            prop.insert("writeOnly".to_string(), json!(true));
+        } else if let Some(def) = &meta.default {
+            prop.insert("default".to_string(), typed_default(def, &f.ty));
+        }
+        // Duration and byte-size values are strings, so their bounds are too
+        let (min_key, max_key) = match unit_type {
+            Some(_) => ("x-minimum", "x-maximum"),
+            None => ("minimum", "maximum"),
+        };
+        if let Some(min) = meta.min {
+            prop.insert(min_key.to_string(), bound(min, &f.ty));
+        }
+        if let Some(max) = meta.max {
+            prop.insert(max_key.to_string(), bound(max, &f.ty));
+        }
+        // Integers are also bounded by their Rust type
+        if let FieldType::Int(kind) = f.ty {
+            prop.insert("x-int-type".to_string(), json!(kind.name()));
+            let (min, max) = kind.range();
+            if meta.min.is_none() {
+                prop.insert("minimum".to_string(), int_value(min));
+            }
+            if meta.max.is_none() {
+                prop.insert("maximum".to_string(), int_value(max));
+            }
+        }
+        if let Some(rx) = &meta.regex {
+            prop.insert("pattern".to_string(), json!(rx));
+        }
+        // `non_empty` is `minLength: 1` in JSON Schema terms
+        let min_len = match (meta.min_len, meta.non_empty) {
+            (Some(n), true) => Some(n.max(1)),
+            (None, true) => Some(1),
+            (n, false) => n,
+        };
+        if let Some(n) = min_len {
+            prop.insert("minLength".to_string(), json!(n));
+        }
+        if let Some(n) = meta.max_len {
+            prop.insert("maxLength".to_string(), json!(n));
+        }
+        if let Some(format) = &meta.format {
+            // JSON Schema calls it `uri`; the other names are kept as they are
+            let name = if format == "url" {
+                "uri"
+            } else {
+                format.as_str()
+            };
+            prop.insert("format".to_string(), json!(name));
+        }
+        if meta.trim {
+            prop.insert("x-trim".to_string(), json!(true));
+        }
+        if meta.lowercase {
+            prop.insert("x-case".to_string(), json!("lowercase"));
+        } else if meta.uppercase {
+            prop.insert("x-case".to_string(), json!("uppercase"));
+        }
+        if meta.ascii_only {
+            prop.insert("x-ascii-only".to_string(), json!(true));
+        }
+        if let Some(msg) = &meta.deprecated {
+            prop.insert("deprecated".to_string(), json!(true));
+            prop.insert("x-deprecation".to_string(), json!(msg));
+        }
+        if !meta.aliases.is_empty() {
+            prop.insert("x-aliases".to_string(), json!(meta.aliases));
+        }
+        if !meta.required_if.is_empty() {
+            let conditions: Vec<Value> = meta
+                .required_if
+                .iter()
+                .map(|c| match &c.equals {
+                    Some(v) => json!({ "field": c.field, "equals": v }),
+                    None => json!({ "field": c.field }),
+                })
+                .collect();
+            prop.insert("x-required-if".to_string(), json!(conditions));
+        }
+        if !meta.conflicts_with.is_empty() {
+            prop.insert("x-conflicts-with".to_string(), json!(meta.conflicts_with));
+        }
+        if let Some(other) = &meta.gt {
+            prop.insert("x-gt".to_string(), json!(other));
+        }
+        if let Some(other) = &meta.lt {
+            prop.insert("x-lt".to_string(), json!(other));
+        }
+
+        if !f.optional && meta.default.is_none() {
+            required.push(f.name.clone());
+        }
+        properties.insert(f.name.clone(), Value::Object(prop));
+    }
+
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": title,
+        "type": "object",
+        "properties": properties,
+        "required": required,
+    })
+}
+
+/// Reads a JSON Schema document produced by [`export`] back into a schema.
+pub fn import(doc: &Value) -> Result<Vec<FieldSchema>, ProfigError> {
+    let invalid = |msg: String| ProfigError::InvalidFormat(format!("JSON Schema: {}", msg));
+
+    let properties = doc
+        .get("properties")
+        .and_then(Value::as_object)
+        .ok_or_else(|| invalid("missing 'properties' object".to_string()))?;
+    let required: Vec<&str> = doc
+        .get("required")
+        .and_then(Value::as_array)
+        .map(|r| r.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+
+    let mut schema = vec![];
+    for (name, prop) in properties {
+        let ty = match (
+            prop.get("type").and_then(Value::as_str),
+            prop.get("x-type").and_then(Value::as_str),
+        ) {
+            (Some("string"), Some("duration")) => FieldType::Duration,
+            (Some("string"), Some("bytes")) => FieldType::Bytes,
+            (Some("string"), Some("path")) => FieldType::Path,
+            (Some("integer"), _) => FieldType::Int(
+                prop.get("x-int-type")
+                    .and_then(Value::as_str)
+                    .and_then(IntKind::from_name)
+                    .unwrap_or(IntKind::I64),
+            ),
+            (Some("number"), _) => FieldType::Float,
+            (Some("string"), _) => FieldType::Str,
+            (Some("boolean"), _) => FieldType::Bool,
+            (other, _) => {
+                return Err(invalid(format!(
+                    "unsupported type {:?} for property '{}'",
+                    other, name
+                )))
+            }
+        };
+        let unit_bound = |key: &str| {
+            let v = prop.get(key)?.as_str()?;
+            match ty {
+                FieldType::Duration => units::parse_duration(v).ok(),
+                _ => units::parse_bytes(v).ok().map(|b| b as f64),
+            }
+        };
+
+        let flag = |key: &str| prop.get(key).and_then(Value::as_bool).unwrap_or(false);
+        let path_checks = strings(prop.get("x-path-checks"));
+        let path_check = |name: &str| path_checks.iter().any(|c| c == name);
+
+        let metadata = MetaField {
+            default: prop.get("default").map(|d| match d {
+                Value::String(s) => s.clone(),
+                other => other.to_string(),
+            }),
+            min: match ty {
+                FieldType::Duration | FieldType::Bytes => unit_bound("x-minimum"),
+                _ => prop.get("minimum").and_then(Value::as_f64),
+            },
+            max: match ty {
+                FieldType::Duration | FieldType::Bytes => unit_bound("x-maximum"),
+                _ => prop.get("maximum").and_then(Value::as_f64),
+            },
+            regex: prop
+                .get("pattern")
+                .and_then(Value::as_str)
+                .map(str::to_string),
+            doc: prop
+                .get("description")
+                .and_then(Value::as_str)
+                .map(str::to_string),
+            secret: prop
+                .get("writeOnly")
+                .and_then(Value::as_bool)
+                .unwrap_or(false),
+            deprecated: match prop.get("deprecated").and_then(Value::as_bool) {
+                Some(true) => Some(
+                    prop.get("x-deprecation")
+                        .and_then(Value::as_str)
+                        .unwrap_or_default()
+                        .to_string(),
+                ),
+                _ => None,
+            },
+            aliases: strings(prop.get("x-aliases")),
+            required_if: prop
+                .get("x-required-if")
+                .and_then(Value::as_array)
+                .map(|a| {
+                    a.iter()
+                        .filter_map(|c| {
+                            Some(Condition {
+                                field: c.get("field")?.as_str()?.to_string(),
+                                equals: c.get("equals").cloned(),
+                            })
+                        })
+                        .collect()
+                })
+                .unwrap_or_default(),
+            conflicts_with: strings(prop.get("x-conflicts-with")),
+            gt: prop.get("x-gt").and_then(Value::as_str).map(str::to_string),
+            lt: prop.get("x-lt").and_then(Value::as_str).map(str::to_string),
+            min_len: prop
+                .get("minLength")
+                .and_then(Value::as_u64)
+                .map(|n| n as usize),
+            max_len: prop
+                .get("maxLength")
+                .and_then(Value::as_u64)
+                .map(|n| n as usize),
+            non_empty: false,
+            trim: flag("x-trim"),
+            lowercase: prop.get("x-case").and_then(Value::as_str) == Some("lowercase"),
+            uppercase: prop.get("x-case").and_then(Value::as_str) == Some("uppercase"),
+            ascii_only: flag("x-ascii-only"),
+            // Formats profig doesn't check (`date-time`, ...) are ignored
+            format: prop
+                .get("format")
+                .and_then(Value::as_str)
+                .map(|f| if f == "uri" { "url" } else { f })
+                .filter(|f| STRING_FORMATS.contains(f) || *f == "port")
+                .map(str::to_string),
+            must_exist: path_check("must_exist"),
+            is_file: path_check("is_file"),
+            is_dir: path_check("is_dir"),
+            readable: path_check("readable"),
+            writable: path_check("writable"),
+            create_dir: path_check("create_dir"),
+        };
+
+        schema.push(FieldSchema {
+            name: name.clone(),
+            ty,
+            optional: !required.contains(&name.as_str()),
+            metadata,
+        });
+    }
+
+    Ok(schema)
+}
+
+/// Title of a JSON Schema document, if it has one.
+pub fn title(doc: &Value) -> Option<&str> {
+    doc.get("title").and_then(Value::as_str)
+}
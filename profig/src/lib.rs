@@ -1,7 +1,26 @@
+pub use config::Profig;
 pub use profig_macros::Profig;
 
+pub mod cli;
+pub mod coerce;
+pub mod config;
+pub mod deprecation;
+pub mod edit;
 pub mod error;
+pub mod format;
 pub mod generator;
+pub mod include;
+pub mod interpolate;
+pub mod json_schema;
 pub mod loader;
+pub mod merge;
+pub mod migrate;
+pub mod paths;
+pub mod profile;
+pub mod secret;
 pub mod types;
+pub mod units;
 pub mod validator;
+
+#[cfg(test)]
+mod test_support;
@@ -1,33 +1,179 @@
+use profig_commons::error::ProfigError;
+use std::error::Error;
+use std::sync::Arc;
+
+use crate::format::{self, ConfigFormat};
+
+/// Lowercased extension of `path`, or an empty string if it has none.
+pub fn extension_of(path: &str) -> String {
+    std::path::Path::new(path)
+        .extension()
+        .and_then(|s| s.to_str())
+        .unwrap_or("")
+        .to_ascii_lowercase()
+}
+
+fn unsupported(ext: &str) -> ProfigError {
+    ProfigError::InvalidFormat(format!("Unsupported or missing file extension: '{}'", ext))
+}
+
+/// Picks the registered format for `path`'s extension, restricted to `allowed`
+/// (the names listed in `#[profig(format = "...")]`).
+pub fn format_for_path(path: &str, allowed: &[&str]) -> Result<Arc<dyn ConfigFormat>, ProfigError> {
+    let ext = extension_of(path);
+
+    for name in allowed {
+        if format::find_by_name(name).is_none() {
+            return Err(ProfigError::InvalidFormat(format!(
+                "Format '{}' is not registered (available: {})",
+                name,
+                format::names().join(", ")
+            )));
+        }
+    }
+
+    match format::find_by_extension(&ext) {
+        Some(f)
+            if allowed
+                .iter()
+                .any(|a| format::find_by_name(a).is_some_and(|g| g.name() == f.name())) =>
+        {
+            Ok(f)
+        }
+        _ => Err(unsupported(&ext)),
+    }
+}
+
+/// Whether `path`'s format only yields strings, so its values need coercing.
+pub fn string_values(path: &str, allowed: &[&str]) -> bool {
+    format_for_path(path, allowed).is_ok_and(|f| f.string_values())
+}
+
+/// Loads `path` as a JSON value, picking the format from its extension.
+///
+/// Files listed under a top-level `include` key (and `!include` tags) are
+/// resolved and merged; see [`crate::include`].
+pub fn load_as_value(path: &str, allowed: &[&str]) -> Result<serde_json::Value, Box<dyn Error>> {
+    Ok(crate::include::load(path, allowed)?)
+}
+
+/// Parses the file at `path` if it exists, picking the format from its extension.
+/// Includes are not resolved.
+pub fn existing_value(path: &str) -> Option<serde_json::Value> {
+    let fmt = format::find_by_extension(&extension_of(path))?;
+    let content = std::fs::read_to_string(path).ok()?;
+
+    fmt.parse(&content).ok()
+}
+
+/// Writes `val` to `path`, picking the format from its extension.
+pub fn save_sample(path: &str, val: &serde_json::Value) -> Result<(), Box<dyn Error>> {
+    let ext = extension_of(path);
+    let fmt = format::find_by_extension(&ext).ok_or_else(|| unsupported(&ext))?;
+
+    std::fs::write(path, fmt.serialize(val)?).map_err(ProfigError::from)?;
+
+    Ok(())
+}
+
+/// Writes `content` to `path` atomically: the data goes to a temporary file in
+/// the same directory, which is then renamed over `path`.
+pub fn write_atomic(path: &str, content: &str) -> Result<(), ProfigError> {
+    use std::io::Write;
+
+    let target = std::path::Path::new(path);
+    let file_name = target
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or_else(|| ProfigError::InvalidFormat(format!("Invalid file path: '{}'", path)))?;
+    let tmp = target.with_file_name(format!(".{}.{}.tmp", file_name, std::process::id()));
+
+    let result = (|| {
+        let mut file = std::fs::File::create(&tmp)?;
+        file.write_all(content.as_bytes())?;
+        file.sync_all()?;
+        std::fs::rename(&tmp, target)
+    })();
+
+    if result.is_err() {
+        let _ = std::fs::remove_file(&tmp);
+    }
+    result.map_err(ProfigError::from)
+}
+
+fn strip_nulls(val: &mut serde_json::Value) {
+    match val {
+        serde_json::Value::Object(map) => {
+            map.retain(|_, v| !v.is_null());
+            map.values_mut().for_each(strip_nulls);
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(strip_nulls),
+        _ => {}
+    }
+}
+
+/// Serializes `val` in the format picked from `path`'s extension and writes it atomically.
+///
+/// `null` entries (unset `Option` fields) are left out, since not every format can represent them.
+pub fn save_value(path: &str, val: &serde_json::Value) -> Result<(), Box<dyn Error>> {
+    let ext = extension_of(path);
+    let fmt = format::find_by_extension(&ext).ok_or_else(|| unsupported(&ext))?;
+
+    let mut val = val.clone();
+    strip_nulls(&mut val);
+    write_atomic(path, &fmt.serialize(&val)?)?;
+
+    Ok(())
+}
+
 #[cfg(feature = "toml")]
 pub mod toml {
+    use crate::format::ConfigFormat;
     use profig_commons::error::ProfigError;
     use std::error::Error;
-    use std::fs::write;
+
+    pub struct TomlFormat;
+
+    impl ConfigFormat for TomlFormat {
+        fn name(&self) -> &'static str {
+            "toml"
+        }
+
+        fn extensions(&self) -> &'static [&'static str] {
+            profig_commons::formats::extensions("toml")
+        }
+
+        fn parse(&self, content: &str) -> Result<serde_json::Value, ProfigError> {
+            let parsed: toml::Value = toml::from_str(content).map_err(|e| ProfigError::Parse {
+                format: "toml",
+                error: e.to_string(),
+            })?;
+            serde_json::to_value(parsed).map_err(|e| ProfigError::Parse {
+                format: "json",
+                error: e.to_string(),
+            })
+        }
+
+        fn serialize(&self, value: &serde_json::Value) -> Result<String, ProfigError> {
+            let toml_val: toml::Value =
+                serde_json::from_value(value.clone()).map_err(|e| ProfigError::Parse {
+                    format: "json",
+                    error: e.to_string(),
+                })?;
+            toml::to_string_pretty(&toml_val).map_err(|e| ProfigError::Parse {
+                format: "toml",
+                error: e.to_string(),
+            })
+        }
+    }
 
     pub fn load_as_value(path: &str) -> Result<serde_json::Value, Box<dyn Error>> {
         let content = std::fs::read_to_string(path).map_err(ProfigError::from)?;
-        let parsed: toml::Value = toml::from_str(&content).map_err(|e| ProfigError::Parse {
-            format: "toml",
-            error: e.to_string(),
-        })?;
-        let json_val = serde_json::to_value(parsed).map_err(|e| ProfigError::Parse {
-            format: "json",
-            error: e.to_string(),
-        })?;
-        Ok(json_val)
+        Ok(TomlFormat.parse(&content)?)
     }
 
     pub fn save_sample(path: &str, val: &serde_json::Value) -> Result<(), Box<dyn Error>> {
-        let toml_val: toml::Value =
-            serde_json::from_value(val.clone()).map_err(|e| ProfigError::Parse {
-                format: "json",
-                error: e.to_string(),
-            })?;
-        let serialized = toml::to_string_pretty(&toml_val).map_err(|e| ProfigError::Parse {
-            format: "toml",
-            error: e.to_string(),
-        })?;
-        write(path, serialized).map_err(ProfigError::from)?;
+        std::fs::write(path, TomlFormat.serialize(val)?).map_err(ProfigError::from)?;
 
         Ok(())
     }
@@ -35,62 +181,151 @@ pub mod toml {
 
 #[cfg(feature = "json")]
 pub mod json {
+    use crate::format::ConfigFormat;
     use profig_commons::error::ProfigError;
     use std::error::Error;
-    use std::fs::write;
 
-    pub fn load_as_value(path: &str) -> Result<serde_json::Value, Box<dyn Error>> {
-        let content = std::fs::read_to_string(path).map_err(ProfigError::from)?;
-        let json_val: serde_json::Value =
-            serde_json::from_str(&content).map_err(|e| ProfigError::Parse {
+    pub struct JsonFormat;
+
+    impl ConfigFormat for JsonFormat {
+        fn name(&self) -> &'static str {
+            "json"
+        }
+
+        fn extensions(&self) -> &'static [&'static str] {
+            profig_commons::formats::extensions("json")
+        }
+
+        fn parse(&self, content: &str) -> Result<serde_json::Value, ProfigError> {
+            serde_json::from_str(&strip_comments(content)).map_err(|e| ProfigError::Parse {
                 format: "json",
                 error: e.to_string(),
-            })?;
-        Ok(json_val)
+            })
+        }
+
+        fn serialize(&self, value: &serde_json::Value) -> Result<String, ProfigError> {
+            serde_json::to_string_pretty(value).map_err(|e| ProfigError::Parse {
+                format: "json",
+                error: e.to_string(),
+            })
+        }
+    }
+
+    pub fn load_as_value(path: &str) -> Result<serde_json::Value, Box<dyn Error>> {
+        let content = std::fs::read_to_string(path).map_err(ProfigError::from)?;
+        Ok(JsonFormat.parse(&content)?)
     }
 
     pub fn save_sample(path: &str, val: &serde_json::Value) -> Result<(), Box<dyn Error>> {
-        let serialized = serde_json::to_string_pretty(val).map_err(|e| ProfigError::Parse {
-            format: "json",
-            error: e.to_string(),
-        })?;
-        write(path, serialized).map_err(ProfigError::from)?;
+        std::fs::write(path, JsonFormat.serialize(val)?).map_err(ProfigError::from)?;
 
         Ok(())
     }
+
+    /// Removes `//` and `/* */` comments (outside of strings) so `.jsonc` files parse.
+    fn strip_comments(src: &str) -> String {
+        let mut out = String::with_capacity(src.len());
+        let mut chars = src.chars().peekable();
+        let mut in_string = false;
+
+        while let Some(c) = chars.next() {
+            if in_string {
+                out.push(c);
+                match c {
+                    '\\' => {
+                        if let Some(n) = chars.next() {
+                            out.push(n);
+                        }
+                    }
+                    '"' => in_string = false,
+                    _ => {}
+                }
+                continue;
+            }
+
+            match (c, chars.peek()) {
+                ('"', _) => {
+                    in_string = true;
+                    out.push(c);
+                }
+                ('/', Some('/')) => {
+                    for n in chars.by_ref() {
+                        if n == '\n' {
+                            out.push('\n');
+                            break;
+                        }
+                    }
+                }
+                ('/', Some('*')) => {
+                    chars.next();
+                    let mut prev = '\0';
+                    for n in chars.by_ref() {
+                        if prev == '*' && n == '/' {
+                            break;
+                        }
+                        if n == '\n' {
+                            out.push('\n');
+                        }
+                        prev = n;
+                    }
+                }
+                _ => out.push(c),
+            }
+        }
+
+        out
+    }
 }
 
 #[cfg(feature = "yaml")]
 pub mod yaml {
+    use crate::format::ConfigFormat;
     use profig_commons::error::ProfigError;
     use std::error::Error;
-    use std::fs::write;
 
-    pub fn load_as_value(path: &str) -> Result<serde_json::Value, Box<dyn Error>> {
-        let content = std::fs::read_to_string(path).map_err(ProfigError::from)?;
-        let parsed: serde_yaml::Value =
-            serde_yaml::from_str(&content).map_err(|e| ProfigError::Parse {
+    pub struct YamlFormat;
+
+    impl ConfigFormat for YamlFormat {
+        fn name(&self) -> &'static str {
+            "yaml"
+        }
+
+        fn extensions(&self) -> &'static [&'static str] {
+            profig_commons::formats::extensions("yaml")
+        }
+
+        fn parse(&self, content: &str) -> Result<serde_json::Value, ProfigError> {
+            let parsed: serde_yaml::Value =
+                serde_yaml::from_str(content).map_err(|e| ProfigError::Parse {
+                    format: "yaml",
+                    error: e.to_string(),
+                })?;
+            serde_json::to_value(parsed).map_err(|e| ProfigError::Parse {
+                format: "json",
+                error: e.to_string(),
+            })
+        }
+
+        fn serialize(&self, value: &serde_json::Value) -> Result<String, ProfigError> {
+            let yaml_val: serde_yaml::Value =
+                serde_json::from_value(value.clone()).map_err(|e| ProfigError::Parse {
+                    format: "json",
+                    error: e.to_string(),
+                })?;
+            serde_yaml::to_string(&yaml_val).map_err(|e| ProfigError::Parse {
                 format: "yaml",
                 error: e.to_string(),
-            })?;
-        let json_val = serde_json::to_value(parsed).map_err(|e| ProfigError::Parse {
-            format: "json",
-            error: e.to_string(),
-        })?;
-        Ok(json_val)
+            })
+        }
+    }
+
+    pub fn load_as_value(path: &str) -> Result<serde_json::Value, Box<dyn Error>> {
+        let content = std::fs::read_to_string(path).map_err(ProfigError::from)?;
+        Ok(YamlFormat.parse(&content)?)
     }
 
     pub fn save_sample(path: &str, val: &serde_json::Value) -> Result<(), Box<dyn Error>> {
-        let yaml_val: serde_yaml::Value =
-            serde_json::from_value(val.clone()).map_err(|e| ProfigError::Parse {
-                format: "json",
-                error: e.to_string(),
-            })?;
-        let serialized = serde_yaml::to_string(&yaml_val).map_err(|e| ProfigError::Parse {
-            format: "yaml",
-            error: e.to_string(),
-        })?;
-        write(path, serialized).map_err(ProfigError::from)?;
+        std::fs::write(path, YamlFormat.serialize(val)?).map_err(ProfigError::from)?;
 
         Ok(())
     }
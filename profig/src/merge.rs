@@ -0,0 +1,39 @@
+use serde_json::Value;
+
+/// Recursively merges `overlay` into `base`.
+///
+/// Objects are merged key by key; any other value in `overlay` (including arrays)
+/// replaces the one in `base`.
+pub fn deep_merge(base: &mut Value, overlay: Value) {
+    match (base, overlay) {
+        (Value::Object(base_map), Value::Object(overlay_map)) => {
+            for (k, v) in overlay_map {
+                match base_map.get_mut(&k) {
+                    Some(existing) => deep_merge(existing, v),
+                    None => {
+                        base_map.insert(k, v);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+/// Sets the value at a dotted path (`server.port`), creating intermediate objects.
+pub fn set_path(root: &mut Value, path: &str, value: Value) {
+    let mut current = root;
+
+    for part in path.split('.') {
+        if !current.is_object() {
+            *current = Value::Object(serde_json::Map::new());
+        }
+        current = current
+            .as_object_mut()
+            .unwrap()
+            .entry(part.to_string())
+            .or_insert(Value::Null);
+    }
+
+    *current = value;
+}
@@ -0,0 +1,96 @@
+use profig_commons::error::ProfigError;
+use serde_json::Value;
+use std::error::Error;
+
+use crate::loader::{format_for_path, write_atomic};
+
+/// Key holding the schema version of a config document.
+pub const VERSION_KEY: &str = "version";
+
+/// Version assumed for documents without a `version` key.
+pub const UNVERSIONED: u64 = 1;
+
+/// Upgrades a document from one schema version to the next.
+pub type MigrationFn = fn(Value) -> Result<Value, Box<dyn Error>>;
+
+/// A migration step from version `from` to `from + 1`.
+pub struct Migration {
+    pub from: u64,
+    pub migrate: MigrationFn,
+}
+
+/// Schema version of `doc`, or [`UNVERSIONED`] if it has none.
+pub fn version_of(doc: &Value) -> Result<u64, ProfigError> {
+    match doc.get(VERSION_KEY) {
+        None | Some(Value::Null) => Ok(UNVERSIONED),
+        Some(v) => v.as_u64().ok_or_else(|| {
+            ProfigError::Validation(format!(
+                "'{}' must be a non-negative integer, got {}",
+                VERSION_KEY, v
+            ))
+        }),
+    }
+}
+
+/// Runs the migration steps needed to bring `doc` up to `current`.
+///
+/// Returns the migrated document and whether anything changed.
+pub fn migrate(
+    mut doc: Value,
+    current: u64,
+    steps: &[Migration],
+) -> Result<(Value, bool), Box<dyn Error>> {
+    let mut version = version_of(&doc)?;
+
+    if version > current {
+        return Err(Box::new(ProfigError::Validation(format!(
+            "Config version {} is newer than the supported version {}",
+            version, current
+        ))));
+    }
+
+    if version == current {
+        return Ok((doc, false));
+    }
+
+    while version < current {
+        let step = steps.iter().find(|s| s.from == version).ok_or_else(|| {
+            ProfigError::Validation(format!(
+                "No migration registered from config version {} to {}",
+                version,
+                version + 1
+            ))
+        })?;
+
+        doc = (step.migrate)(doc).map_err(|e| {
+            ProfigError::Validation(format!("Migration from version {} failed: {}", version, e))
+        })?;
+        version += 1;
+    }
+
+    if let Some(map) = doc.as_object_mut() {
+        map.insert(VERSION_KEY.to_string(), Value::from(current));
+    }
+
+    Ok((doc, true))
+}
+
+/// Migrates the file at `path` in place. Returns `true` if the file was rewritten.
+///
+/// The file is read without resolving includes, so included files are left untouched.
+pub fn migrate_file(
+    path: &str,
+    allowed: &[&str],
+    current: u64,
+    steps: &[Migration],
+) -> Result<bool, Box<dyn Error>> {
+    let fmt = format_for_path(path, allowed)?;
+    let content = std::fs::read_to_string(path).map_err(ProfigError::from)?;
+
+    let (doc, changed) = migrate(fmt.parse(&content)?, current, steps)?;
+    if changed {
+        write_atomic(path, &fmt.serialize(&doc)?)?;
+    }
+
+    Ok(changed)
+}
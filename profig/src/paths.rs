@@ -0,0 +1,167 @@
+use profig_commons::error::ProfigError;
+use profig_commons::types::{FieldSchema, FieldType};
+use serde_json::Value;
+use std::path::{Component, Path, PathBuf};
+
+/// Directory that relative paths in the config file at `config_path` are resolved against.
+pub fn base_dir(config_path: &str) -> PathBuf {
+    let parent = Path::new(config_path)
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+
+    std::path::absolute(parent).unwrap_or_else(|_| parent.to_path_buf())
+}
+
+/// Absolute form of `path`, relative to the working directory if it isn't absolute.
+pub fn absolute(path: &Path) -> PathBuf {
+    std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// `path` resolved against `base` if it is relative.
+fn resolve_one(path: &str, base: &Path) -> PathBuf {
+    let path = Path::new(path);
+    if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        // `./data` becomes `<base>/data` rather than `<base>/./data`
+        let relative: PathBuf = path
+            .components()
+            .filter(|c| !matches!(c, Component::CurDir))
+            .collect();
+        base.join(relative)
+    }
+}
+
+/// Rewrites relative `PathBuf` fields (including their defaults) as paths under `base`.
+pub fn resolve(config: &mut Value, schema: &[FieldSchema], base: &Path) -> Result<(), ProfigError> {
+    let Some(map) = config.as_object_mut() else {
+        return Ok(());
+    };
+
+    for f in schema.iter().filter(|f| matches!(f.ty, FieldType::Path)) {
+        let value = match map.get(&f.name) {
+            Some(Value::String(s)) => s.clone(),
+            None | Some(Value::Null) => match &f.metadata.default {
+                Some(def) => def.clone(),
+                None => continue,
+            },
+            // Left for the validator to report as a type mismatch
+            Some(_) => continue,
+        };
+
+        let resolved = resolve_one(&value, base);
+        map.insert(
+            f.name.clone(),
+            Value::String(resolved.to_string_lossy().into_owned()),
+        );
+    }
+
+    Ok(())
+}
+
+/// Creates the directories of `create_dir` fields that don't exist yet.
+/// Run after validation, so an invalid config leaves the file system untouched.
+pub fn create_dirs(config: &Value, schema: &[FieldSchema]) -> Result<(), ProfigError> {
+    for f in schema.iter().filter(|f| f.metadata.create_dir) {
+        let Some(Value::String(s)) = config.get(&f.name) else {
+            continue;
+        };
+        let path = absolute(Path::new(s));
+        if !path.exists() {
+            std::fs::create_dir_all(&path).map_err(|e| {
+                ProfigError::Validation(format!(
+                    "Path '{}' could not be created: {}. Field: '{}'",
+                    path.display(),
+                    e,
+                    &f.name
+                ))
+            })?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Undoes `resolve` before a config is written to a file in `base`: values still
+/// pointing where the `existing` file's values pointed are written as they were
+/// there, and other paths under `base` become relative to it.
+pub fn relativize(
+    config: &mut Value,
+    schema: &[FieldSchema],
+    base: &Path,
+    existing: Option<&Value>,
+) {
+    let Some(map) = config.as_object_mut() else {
+        return;
+    };
+
+    for f in schema.iter().filter(|f| matches!(f.ty, FieldType::Path)) {
+        if let Some(Value::String(s)) = map.get_mut(&f.name) {
+            let original = existing
+                .and_then(|e| e.get(&f.name))
+                .and_then(Value::as_str)
+                .filter(|o| resolve_one(o, base) == Path::new(s.as_str()));
+            if let Some(original) = original {
+                *s = original.to_string();
+            } else if let Ok(relative) = Path::new(s.as_str()).strip_prefix(base) {
+                let relative = if relative.as_os_str().is_empty() {
+                    Path::new(".")
+                } else {
+                    relative
+                };
+                *s = relative.to_string_lossy().into_owned();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{field, field_with};
+    use profig_commons::types::MetaField;
+    use serde_json::json;
+
+    #[test]
+    fn create_dir_is_validated_without_creating_it() {
+        let root = std::env::temp_dir().join(format!("profig-paths-{}", std::process::id()));
+        let dir = root.join("data/cache");
+        let create_dir = MetaField {
+            create_dir: true,
+            ..MetaField::default()
+        };
+        let schema = [field_with("data_dir", FieldType::Path, create_dir)];
+        let mut config = json!({ "data_dir": dir.to_string_lossy() });
+
+        crate::validator::validate_fields(&mut config, &schema).unwrap();
+        assert!(!root.exists());
+
+        create_dirs(&config, &schema).unwrap();
+        assert!(dir.is_dir());
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn relativize_keeps_the_written_form() {
+        let base = Path::new("/etc/myapp");
+        let schema = [
+            field("data_dir", FieldType::Path),
+            field("log_dir", FieldType::Path),
+            field("tmp_dir", FieldType::Path),
+        ];
+        let existing = json!({ "data_dir": "./data" });
+        let mut config = json!({
+            "data_dir": "/etc/myapp/data",
+            "log_dir": "/etc/myapp/logs/app",
+            "tmp_dir": "/tmp",
+        });
+
+        relativize(&mut config, &schema, base, Some(&existing));
+
+        assert_eq!(
+            config,
+            json!({ "data_dir": "./data", "log_dir": "logs/app", "tmp_dir": "/tmp" })
+        );
+    }
+}
@@ -0,0 +1,97 @@
+use profig_commons::error::ProfigError;
+use serde_json::Value;
+
+use crate::merge::deep_merge;
+use crate::migrate::VERSION_KEY;
+
+/// Name of the section every profile is merged over.
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// Profile named by the environment variable `var`, or [`DEFAULT_PROFILE`] if unset or empty.
+pub fn from_env(var: &str) -> String {
+    std::env::var(var)
+        .ok()
+        .filter(|p| !p.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_PROFILE.to_string())
+}
+
+/// Profiles defined in a document made of profile sections, starting with [`DEFAULT_PROFILE`].
+pub fn names(doc: &Value) -> Vec<String> {
+    let mut names = vec![DEFAULT_PROFILE.to_string()];
+    if let Some(sections) = doc.as_object() {
+        names.extend(
+            sections
+                .keys()
+                .filter(|k| *k != DEFAULT_PROFILE && *k != VERSION_KEY)
+                .cloned(),
+        );
+    }
+
+    names
+}
+
+/// Resolves `profile` from a document made of profile sections.
+///
+/// The `default` section (if any) is used as the base and the chosen section is
+/// deep-merged over it. A top-level `version` key belongs to the whole document
+/// and is kept.
+pub fn select(doc: Value, profile: &str) -> Result<Value, ProfigError> {
+    let Value::Object(mut sections) = doc else {
+        return Err(ProfigError::InvalidFormat(
+            "Expected profile sections at the top level of the config".to_string(),
+        ));
+    };
+    let version = sections.remove(VERSION_KEY);
+
+    let mut merged = sections
+        .remove(DEFAULT_PROFILE)
+        .unwrap_or_else(|| Value::Object(serde_json::Map::new()));
+
+    if profile != DEFAULT_PROFILE {
+        let overlay = sections.remove(profile).ok_or_else(|| {
+            let mut available: Vec<&str> = sections.keys().map(String::as_str).collect();
+            available.insert(0, DEFAULT_PROFILE);
+            ProfigError::Validation(format!(
+                "Profile '{}' not found (available: {})",
+                profile,
+                available.join(", ")
+            ))
+        })?;
+        deep_merge(&mut merged, overlay);
+    }
+    if let (Some(v), Some(map)) = (version, merged.as_object_mut()) {
+        map.insert(VERSION_KEY.to_string(), v);
+    }
+
+    Ok(merged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn selected_profile_is_merged_over_default() {
+        let doc = json!({
+            "default": { "host": "localhost", "db": { "pool": 4, "name": "app" } },
+            "production": { "host": "example.com", "db": { "pool": 32 } },
+        });
+
+        let merged = select(doc, "production").unwrap();
+
+        assert_eq!(
+            merged,
+            json!({ "host": "example.com", "db": { "pool": 32, "name": "app" } })
+        );
+    }
+
+    #[test]
+    fn version_key_is_kept() {
+        let doc = json!({ "version": 2, "default": { "workers": 4 } });
+
+        let merged = select(doc, DEFAULT_PROFILE).unwrap();
+
+        assert_eq!(merged, json!({ "workers": 4, "version": 2 }));
+    }
+}
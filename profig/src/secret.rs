@@ -0,0 +1,251 @@
+use profig_commons::error::ProfigError;
+use profig_commons::types::{FieldSchema, MetaField};
+use serde_json::Value;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Placeholder shown instead of a secret value.
+pub const REDACTED: &str = "***";
+
+/// Suffix of the key that points to a file holding a secret (`password_file`).
+pub const FILE_SUFFIX: &str = "_file";
+
+/// Debug/Display stand-in for a secret field.
+pub struct Redacted;
+
+impl fmt::Debug for Redacted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+impl fmt::Display for Redacted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+/// Text to show for `raw` in messages: the value itself, or [`REDACTED`] for secret fields.
+pub fn shown(raw: impl fmt::Display, meta: &MetaField) -> String {
+    if meta.secret {
+        REDACTED.to_string()
+    } else {
+        raw.to_string()
+    }
+}
+
+/// `path` from a `<field>_file` key, resolved against `base_dir` like `PathBuf` fields.
+fn secret_path(path: &str, base_dir: Option<&Path>) -> PathBuf {
+    match base_dir {
+        Some(dir) => dir.join(path),
+        None => PathBuf::from(path),
+    }
+}
+
+/// For every secret field, replaces a `<field>_file` key with the contents of that file.
+/// Relative paths are read from `base_dir` (the config file's directory) if given.
+///
+/// Trailing newlines are trimmed, so files written by `echo` or Docker/Kubernetes
+/// secrets work as-is.
+pub fn resolve_files(
+    config: &mut Value,
+    schema: &[FieldSchema],
+    base_dir: Option<&Path>,
+) -> Result<(), ProfigError> {
+    let Some(map) = config.as_object_mut() else {
+        return Ok(());
+    };
+
+    for f in schema.iter().filter(|f| f.metadata.secret) {
+        let file_key = format!("{}{}", f.name, FILE_SUFFIX);
+        let Some(path) = map.remove(&file_key) else {
+            continue;
+        };
+
+        let path = match path {
+            Value::String(p) => p,
+            Value::Null => continue,
+            other => {
+                return Err(ProfigError::Validation(format!(
+                    "'{}' must be a file path, got {}",
+                    file_key, other
+                )))
+            }
+        };
+
+        if map.get(&f.name).is_some_and(|v| !v.is_null()) {
+            return Err(ProfigError::Validation(format!(
+                "Both '{}' and '{}' are set; use only one",
+                f.name, file_key
+            )));
+        }
+
+        let content = std::fs::read_to_string(secret_path(&path, base_dir)).map_err(|e| {
+            ProfigError::Validation(format!(
+                "Cannot read '{}' for secret field '{}': {}",
+                path, f.name, e
+            ))
+        })?;
+        map.insert(
+            f.name.clone(),
+            Value::String(content.trim_end_matches(['\n', '\r']).to_string()),
+        );
+    }
+
+    Ok(())
+}
+
+/// Replaces the values of secret fields in `message` with [`REDACTED`], for errors
+/// that quote values without knowing which field they came from (like serde's).
+///
+/// Only whole quoted values are replaced: `"hunter2"` as serde quotes strings, and
+/// `` `4321` `` as it quotes numbers and booleans, so a secret `1` doesn't garble
+/// other numbers in the message.
+pub fn scrub(message: &str, config: &Value, schema: &[FieldSchema]) -> String {
+    let mut message = message.to_string();
+
+    for f in schema.iter().filter(|f| f.metadata.secret) {
+        let raw = match config.get(&f.name) {
+            None | Some(Value::Null) => continue,
+            Some(Value::String(s)) => s.clone(),
+            Some(other) => other.to_string(),
+        };
+        message = message
+            .replace(&format!("{:?}", raw), &format!("\"{}\"", REDACTED))
+            .replace(&format!("`{}`", raw), &format!("`{}`", REDACTED));
+    }
+
+    message
+}
+
+/// For every secret field the file being replaced (`existing`) reads from `<field>_file`,
+/// writes that key again instead of the value, so saving doesn't copy the secret into
+/// the config. The referenced file (relative to `base_dir`) must still hold the value
+/// being saved.
+pub fn keep_files(
+    config: &mut Value,
+    schema: &[FieldSchema],
+    existing: &Value,
+    base_dir: &Path,
+) -> Result<(), ProfigError> {
+    let Some(map) = config.as_object_mut() else {
+        return Ok(());
+    };
+
+    for f in schema.iter().filter(|f| f.metadata.secret) {
+        let file_key = format!("{}{}", f.name, FILE_SUFFIX);
+        let Some(Value::String(path)) = existing.get(&file_key) else {
+            continue;
+        };
+
+        let value = match map.remove(&f.name) {
+            None | Some(Value::Null) => continue,
+            Some(Value::String(s)) => s,
+            Some(other) => other.to_string(),
+        };
+        let content = std::fs::read_to_string(secret_path(path, Some(base_dir))).map_err(|e| {
+            ProfigError::Validation(format!(
+                "Cannot read '{}' for secret field '{}': {}",
+                path, f.name, e
+            ))
+        })?;
+        if content.trim_end_matches(['\n', '\r']) != value {
+            return Err(ProfigError::Validation(format!(
+                "Secret field '{}' no longer matches '{}' (from '{}'); update that file instead of saving the value",
+                f.name, path, file_key
+            )));
+        }
+
+        map.insert(file_key, Value::String(path.clone()));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{field, field_with};
+    use profig_commons::types::{FieldType, IntKind};
+    use serde_json::json;
+
+    fn secret(name: &str, ty: FieldType) -> FieldSchema {
+        let metadata = MetaField {
+            secret: true,
+            ..MetaField::default()
+        };
+        field_with(name, ty, metadata)
+    }
+
+    #[test]
+    fn scrub_hides_secret_values_of_any_type() {
+        let schema = [
+            secret("password", FieldType::Str),
+            secret("pin", FieldType::Int(IntKind::U32)),
+            field("user", FieldType::Str),
+        ];
+        let config = json!({ "password": "hunter2", "pin": 4321, "user": "admin" });
+
+        let message = scrub(
+            r#"invalid value: string "hunter2", expected a pin other than `4321` for "admin""#,
+            &config,
+            &schema,
+        );
+        assert_eq!(
+            message,
+            r#"invalid value: string "***", expected a pin other than `***` for "admin""#
+        );
+    }
+
+    #[test]
+    fn scrub_leaves_other_numbers_and_words_alone() {
+        let schema = [
+            secret("level", FieldType::Int(IntKind::U8)),
+            secret("debug", FieldType::Bool),
+        ];
+        let config = json!({ "level": 1, "debug": true });
+
+        let message = scrub(
+            "invalid type: integer `1`, expected a string at line 10 column 1 (true for `true`)",
+            &config,
+            &schema,
+        );
+        assert_eq!(
+            message,
+            "invalid type: integer `***`, expected a string at line 10 column 1 (true for `***`)"
+        );
+    }
+
+    #[test]
+    fn resolve_files_reads_relative_paths_from_the_base_dir() {
+        let dir = std::env::temp_dir().join(format!("profig-secret-dir-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("secrets")).unwrap();
+        std::fs::write(dir.join("secrets/db"), "hunter2\n").unwrap();
+
+        let schema = [secret("password", FieldType::Str)];
+        let mut config = json!({ "password_file": "secrets/db" });
+        resolve_files(&mut config, &schema, Some(&dir)).unwrap();
+
+        assert_eq!(config, json!({ "password": "hunter2" }));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn keep_files_writes_the_file_key_back() {
+        let secret_file =
+            std::env::temp_dir().join(format!("profig-secret-{}", std::process::id()));
+        std::fs::write(&secret_file, "hunter2\n").unwrap();
+        let secret_path = secret_file.to_string_lossy().into_owned();
+
+        let schema = [secret("password", FieldType::Str)];
+        let existing = json!({ "password_file": secret_path });
+
+        let mut config = json!({ "password": "hunter2" });
+        keep_files(&mut config, &schema, &existing, &std::env::temp_dir()).unwrap();
+        assert_eq!(config, json!({ "password_file": secret_path }));
+
+        let mut changed = json!({ "password": "changed" });
+        let err = keep_files(&mut changed, &schema, &existing, &std::env::temp_dir()).unwrap_err();
+        assert!(!err.to_string().contains("changed"), "{}", err);
+    }
+}
@@ -0,0 +1,16 @@
+use profig_commons::types::{FieldSchema, FieldType, MetaField};
+
+/// A required field of type `ty` without any constraints.
+pub fn field(name: &str, ty: FieldType) -> FieldSchema {
+    field_with(name, ty, MetaField::default())
+}
+
+/// A required field of type `ty` with the given attributes.
+pub fn field_with(name: &str, ty: FieldType, metadata: MetaField) -> FieldSchema {
+    FieldSchema {
+        name: name.to_string(),
+        ty,
+        optional: false,
+        metadata,
+    }
+}
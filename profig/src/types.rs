@@ -1 +1,3 @@
-pub use profig_commons::types::{FieldSchema, FieldType, MetaField};
+pub use profig_commons::types::{
+    Condition, FieldSchema, FieldType, IntKind, MetaField, STRING_FORMATS,
+};
@@ -0,0 +1,172 @@
+use profig_commons::types::{FieldSchema, FieldType};
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{json, Value};
+use std::fmt;
+use std::str::FromStr;
+
+pub use profig_commons::units::{format_bytes, format_duration, parse_bytes, parse_duration};
+
+/// A size in bytes, read from `"512MiB"`-style strings or plain byte counts and
+/// written back as a string.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ByteSize(pub u64);
+
+impl ByteSize {
+    pub fn as_u64(self) -> u64 {
+        self.0
+    }
+}
+
+impl fmt::Display for ByteSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&format_bytes(self.0))
+    }
+}
+
+impl FromStr for ByteSize {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_bytes(s).map(ByteSize)
+    }
+}
+
+impl Serialize for ByteSize {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format_bytes(self.0))
+    }
+}
+
+impl<'de> Deserialize<'de> for ByteSize {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct ByteSizeVisitor;
+
+        impl Visitor<'_> for ByteSizeVisitor {
+            type Value = ByteSize;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a byte size such as \"512MiB\" or a number of bytes")
+            }
+
+            fn visit_u64<E: de::Error>(self, v: u64) -> Result<ByteSize, E> {
+                Ok(ByteSize(v))
+            }
+
+            fn visit_i64<E: de::Error>(self, v: i64) -> Result<ByteSize, E> {
+                u64::try_from(v)
+                    .map(ByteSize)
+                    .map_err(|_| E::custom("byte size cannot be negative"))
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<ByteSize, E> {
+                v.parse().map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_any(ByteSizeVisitor)
+    }
+}
+
+/// Seconds in a duration value: a string like `"1h30m"`, a number of seconds, or
+/// the `{ secs, nanos }` form serde uses for `std::time::Duration`.
+pub fn duration_secs(value: &Value) -> Result<f64, String> {
+    match value {
+        Value::String(s) => parse_duration(s),
+        Value::Number(n) => n
+            .as_f64()
+            .filter(|v| *v >= 0.0)
+            .ok_or_else(|| "duration cannot be negative".to_string()),
+        Value::Object(map) => {
+            let secs = map.get("secs").and_then(Value::as_u64);
+            let nanos = map.get("nanos").and_then(Value::as_u64).unwrap_or(0);
+            secs.map(|s| s as f64 + nanos as f64 / 1e9)
+                .ok_or_else(|| "expected a duration such as \"30s\"".to_string())
+        }
+        _ => Err("expected a duration such as \"30s\"".to_string()),
+    }
+}
+
+/// Number of bytes in a byte-size value: a string like `"512MiB"` or a number of bytes.
+pub fn byte_count(value: &Value) -> Result<u64, String> {
+    match value {
+        Value::String(s) => parse_bytes(s),
+        Value::Number(n) => n
+            .as_u64()
+            .ok_or_else(|| "expected a whole, non-negative number of bytes".to_string()),
+        _ => Err("expected a byte size such as \"512MiB\"".to_string()),
+    }
+}
+
+/// The value serde deserializes into a `std::time::Duration`.
+///
+/// Durations past `u64::MAX` seconds are clamped to the largest one `Duration` holds.
+pub fn duration_value(secs: f64) -> Value {
+    let nanos = (secs * 1e9).round() as u128;
+    match u64::try_from(nanos / 1_000_000_000) {
+        Ok(secs) => json!({ "secs": secs, "nanos": (nanos % 1_000_000_000) as u32 }),
+        Err(_) => json!({ "secs": u64::MAX, "nanos": 999_999_999 }),
+    }
+}
+
+/// Rewrites `Duration` fields from serde's `{ secs, nanos }` form into strings like
+/// `"1h30m"`, so saved files stay readable.
+pub fn humanize(config: &mut Value, schema: &[FieldSchema]) {
+    let Some(map) = config.as_object_mut() else {
+        return;
+    };
+
+    for f in schema
+        .iter()
+        .filter(|f| matches!(f.ty, FieldType::Duration))
+    {
+        if let Some(v) = map.get_mut(&f.name) {
+            if let (Value::Object(_), Ok(secs)) = (&*v, duration_secs(v)) {
+                *v = Value::String(format_duration(secs));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn duration_value_deserializes_into_a_duration() {
+        let d: Duration = serde_json::from_value(duration_value(5400.25)).unwrap();
+        assert_eq!(d, Duration::from_millis(5_400_250));
+    }
+
+    #[test]
+    fn duration_value_clamps_instead_of_wrapping() {
+        // 2^64 seconds wrapped to 0 when the seconds were cast straight to u64
+        let v = duration_value(18_446_744_073_709_551_616.0);
+        assert_eq!(v, json!({ "secs": u64::MAX, "nanos": 999_999_999 }));
+        let d: Duration = serde_json::from_value(v).unwrap();
+        assert_eq!(d, Duration::MAX);
+
+        assert_eq!(duration_value(f64::MAX)["secs"], json!(u64::MAX));
+    }
+
+    #[test]
+    fn duration_secs_reads_strings_numbers_and_serde_form() {
+        assert_eq!(duration_secs(&json!("1h30m")), Ok(5400.0));
+        assert_eq!(duration_secs(&json!(90)), Ok(90.0));
+        assert_eq!(
+            duration_secs(&json!({ "secs": 2, "nanos": 500_000_000 })),
+            Ok(2.5)
+        );
+        assert!(duration_secs(&json!(-1)).is_err());
+        assert!(duration_secs(&json!(true)).is_err());
+    }
+
+    #[test]
+    fn byte_count_rejects_negative_and_fractional_numbers() {
+        assert_eq!(byte_count(&json!("1.5 kb")), Ok(1500));
+        assert_eq!(byte_count(&json!(512)), Ok(512));
+        assert!(byte_count(&json!(-1)).is_err());
+        assert!(byte_count(&json!(1.5)).is_err());
+    }
+}
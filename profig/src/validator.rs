@@ -1,10 +1,14 @@
 use profig_commons::error::ProfigError;
-use profig_commons::types::FieldType;
+use profig_commons::types::{FieldType, IntKind, MetaField};
 use regex::Regex;
 use serde_json::{Value};
 use std::error::Error;
+use std::path::Path;
 
+use crate::paths;
+use crate::secret::shown;
 use crate::types::FieldSchema;
+use crate::units;
 
 fn less_than_min(value: &Value, min: f64) -> bool {
     match value {
@@ -41,172 +45,936 @@ fn valid_regex(value: &Value, pattern: &str) -> Result<bool, regex::Error> {
     }
 }
 
+/// Human-readable name of a field format, used in messages and docs.
+pub fn format_description(format: &str) -> &str {
+    match format {
+        "url" => "URL",
+        "email" => "email address",
+        "ip" => "IP address",
+        "ipv4" => "IPv4 address",
+        "ipv6" => "IPv6 address",
+        "hostname" => "hostname",
+        "socket_addr" => "socket address (ip:port)",
+        "uuid" => "UUID",
+        "port" => "port number (1-65535)",
+        other => other,
+    }
+}
+
+fn check_hostname(s: &str) -> Result<(), String> {
+    let name = s.strip_suffix('.').unwrap_or(s);
+    if name.is_empty() {
+        return Err("empty hostname".to_string());
+    }
+    if name.len() > 253 {
+        return Err("longer than 253 characters".to_string());
+    }
+
+    for label in name.split('.') {
+        if label.is_empty() {
+            return Err("empty label".to_string());
+        }
+        if label.len() > 63 {
+            return Err(format!("label '{}' is longer than 63 characters", label));
+        }
+        if label.starts_with('-') || label.ends_with('-') {
+            return Err(format!("label '{}' starts or ends with '-'", label));
+        }
+        if let Some(c) = label
+            .chars()
+            .find(|c| !c.is_ascii_alphanumeric() && *c != '-')
+        {
+            return Err(format!("invalid character '{}'", c));
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks a URL the way `url::Url` parses it: a scheme, then either `//` with an
+/// authority or an opaque part (`mailto:ops@example.com`, `unix:/run/app.sock`).
+/// Only the special schemes (`http`, `https`, `ws`, `wss`, `ftp`) require a host,
+/// and only their hosts must be valid hostnames or IP addresses.
+fn check_url(s: &str) -> Result<(), String> {
+    let Some((scheme, rest)) = s.split_once(':') else {
+        return Err("missing scheme (expected 'scheme:...')".to_string());
+    };
+
+    let mut chars = scheme.chars();
+    if !chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        || !chars.all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+    {
+        return Err(format!("invalid scheme '{}'", scheme));
+    }
+    if let Some(c) = s.chars().find(|c| c.is_whitespace()) {
+        return Err(format!("contains whitespace {:?}", c));
+    }
+    let special = ["http", "https", "ws", "wss", "ftp"]
+        .iter()
+        .any(|s| scheme.eq_ignore_ascii_case(s));
+    let rest = match rest.strip_prefix("//") {
+        Some(rest) => rest,
+        // Like `url::Url`, `http:example.com` means `http://example.com`
+        None if special => rest.trim_start_matches('/'),
+        None => return Ok(()),
+    };
+
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    let host_port = authority.rsplit_once('@').map_or(authority, |(_, hp)| hp);
+
+    let (host, port) = if let Some(v6) = host_port.strip_prefix('[') {
+        let (ip, after) = v6
+            .split_once(']')
+            .ok_or_else(|| "unterminated IPv6 host".to_string())?;
+        ip.parse::<std::net::Ipv6Addr>()
+            .map_err(|_| format!("invalid IPv6 host '{}'", ip))?;
+        (ip, after.strip_prefix(':'))
+    } else {
+        match host_port.rsplit_once(':') {
+            Some((h, p)) => (h, Some(p)),
+            None => (host_port, None),
+        }
+    };
+
+    if host.is_empty() && special {
+        return Err("missing host".to_string());
+    }
+    let named = !host.is_empty() && !host_port.starts_with('[');
+    if special && named && host.parse::<std::net::Ipv4Addr>().is_err() {
+        check_hostname(host).map_err(|e| format!("invalid host '{}': {}", host, e))?;
+    }
+    if let Some(p) = port {
+        if !p.is_empty() && p.parse::<u16>().is_err() {
+            return Err(format!("invalid port '{}'", p));
+        }
+    }
+
+    Ok(())
+}
+
+fn check_email(s: &str) -> Result<(), String> {
+    let Some((local, domain)) = s.rsplit_once('@') else {
+        return Err("missing '@'".to_string());
+    };
+
+    if local.is_empty() {
+        return Err("empty local part".to_string());
+    }
+    if local.len() > 64 {
+        return Err("local part is longer than 64 characters".to_string());
+    }
+    if let Some(c) = local
+        .chars()
+        .find(|c| c.is_whitespace() || c.is_control() || "@<>()[]\\,;:\"".contains(*c))
+    {
+        return Err(format!("invalid character {:?} in local part", c));
+    }
+    if !domain.contains('.') {
+        return Err(format!("domain '{}' has no '.'", domain));
+    }
+    check_hostname(domain).map_err(|e| format!("invalid domain '{}': {}", domain, e))
+}
+
+fn check_uuid(s: &str) -> Result<(), String> {
+    let groups: Vec<&str> = s.split('-').collect();
+    let lens: Vec<usize> = groups.iter().map(|g| g.len()).collect();
+    if lens != [8, 4, 4, 4, 12] {
+        return Err("expected 8-4-4-4-12 hex digits".to_string());
+    }
+    if let Some(c) = s.chars().find(|c| !c.is_ascii_hexdigit() && *c != '-') {
+        return Err(format!("invalid character '{}'", c));
+    }
+
+    Ok(())
+}
+
+/// Checks `s` against a string format; the error explains what is wrong.
+pub fn check_format(s: &str, format: &str) -> Result<(), String> {
+    match format {
+        "url" => check_url(s),
+        "email" => check_email(s),
+        "ip" => s
+            .parse::<std::net::IpAddr>()
+            .map(|_| ())
+            .map_err(|e| e.to_string()),
+        "ipv4" => s
+            .parse::<std::net::Ipv4Addr>()
+            .map(|_| ())
+            .map_err(|e| e.to_string()),
+        "ipv6" => s
+            .parse::<std::net::Ipv6Addr>()
+            .map(|_| ())
+            .map_err(|e| e.to_string()),
+        "hostname" => check_hostname(s),
+        "socket_addr" => s
+            .parse::<std::net::SocketAddr>()
+            .map(|_| ())
+            .map_err(|_| "expected 'ip:port' or '[ipv6]:port'".to_string()),
+        "uuid" => check_uuid(s),
+        other => Err(format!("unknown format '{}'", other)),
+    }
+}
+
+/// Applies `trim` and `lowercase`/`uppercase` to a string value.
+fn normalize_str(s: &mut String, meta: &MetaField) {
+    if meta.trim {
+        let trimmed = s.trim();
+        if trimmed.len() != s.len() {
+            *s = trimmed.to_string();
+        }
+    }
+    if meta.lowercase {
+        *s = s.to_lowercase();
+    } else if meta.uppercase {
+        *s = s.to_uppercase();
+    }
+}
+
+fn check_str(s: &str, f: &FieldSchema) -> Result<(), ProfigError> {
+    let meta = &f.metadata;
+    let len = s.chars().count();
+
+    if meta.non_empty && s.is_empty() {
+        return Err(ProfigError::Validation(format!(
+            "Value must not be empty. Field: '{}'",
+            &f.name
+        )));
+    }
+    if let Some(min) = meta.min_len {
+        if len < min {
+            return Err(ProfigError::Validation(format!(
+                "Value '{}' is shorter than {} characters ({}). Field: '{}'",
+                shown(s, meta),
+                min,
+                len,
+                &f.name
+            )));
+        }
+    }
+    if let Some(max) = meta.max_len {
+        if len > max {
+            return Err(ProfigError::Validation(format!(
+                "Value '{}' is longer than {} characters ({}). Field: '{}'",
+                shown(s, meta),
+                max,
+                len,
+                &f.name
+            )));
+        }
+    }
+    if meta.ascii_only && !s.is_ascii() {
+        return Err(ProfigError::Validation(format!(
+            "Value '{}' contains non-ASCII characters. Field: '{}'",
+            shown(s, meta),
+            &f.name
+        )));
+    }
+    if let Some(format) = &meta.format {
+        if let Err(reason) = check_format(s, format) {
+            return Err(ProfigError::Validation(format!(
+                "Value '{}' is not a valid {}: {}. Field: '{}'",
+                shown(s, meta),
+                format_description(format),
+                reason,
+                &f.name
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/// Closest ancestor of a missing `path` that exists.
+fn existing_ancestor(path: &Path) -> Option<&Path> {
+    path.ancestors().skip(1).find(|p| p.exists())
+}
+
+/// Whether the permissions of an existing `path` allow writing, or a missing
+/// `path` could be created (its closest existing ancestor is a writable directory).
+/// Only metadata is read; nothing is opened or created.
+fn is_writable(path: &Path) -> bool {
+    match std::fs::metadata(path) {
+        Ok(meta) => !meta.permissions().readonly(),
+        Err(_) => existing_ancestor(path).is_some_and(|dir| dir.is_dir() && is_writable(dir)),
+    }
+}
+
+/// Checks `must_exist`, `is_file`, `is_dir`, `readable`, `writable` and `create_dir`
+/// for a path field, without touching the file system (see `paths::create_dirs`).
+/// Errors show the absolute path.
+fn check_path(s: &str, f: &FieldSchema) -> Result<(), ProfigError> {
+    let meta = &f.metadata;
+    let path = paths::absolute(Path::new(s));
+    let fail = |what: String| {
+        ProfigError::Validation(format!(
+            "Path '{}' {}. Field: '{}'",
+            path.display(),
+            what,
+            &f.name
+        ))
+    };
+
+    // Missing `create_dir` directories are created after validation
+    if meta.create_dir && !path.exists() {
+        return match existing_ancestor(&path) {
+            Some(dir) if dir.is_dir() && is_writable(dir) => Ok(()),
+            Some(dir) => Err(fail(format!(
+                "cannot be created, '{}' is not a writable directory",
+                dir.display()
+            ))),
+            None => Err(fail("cannot be created".to_string())),
+        };
+    }
+
+    let must_exist = meta.must_exist || meta.is_file || meta.is_dir || meta.readable;
+    if must_exist && !path.exists() {
+        return Err(fail("does not exist".to_string()));
+    }
+    if meta.is_file && !path.is_file() {
+        return Err(fail("is not a file".to_string()));
+    }
+    if (meta.is_dir || meta.create_dir) && !path.is_dir() {
+        return Err(fail("is not a directory".to_string()));
+    }
+    if meta.readable {
+        let readable = if path.is_dir() {
+            std::fs::read_dir(&path).is_ok()
+        } else {
+            std::fs::File::open(&path).is_ok()
+        };
+        if !readable {
+            return Err(fail("is not readable".to_string()));
+        }
+    }
+    if meta.writable && !is_writable(&path) {
+        return Err(fail("is not writable".to_string()));
+    }
+
+    Ok(())
+}
+
+/// Rejects integers that don't fit the field's integer type
+/// (`70000` for a `u16`, `-1` for a `usize`), which serde would only report
+/// without naming the field.
+fn check_int(v: &Value, kind: IntKind, f: &FieldSchema) -> Result<(), ProfigError> {
+    let Value::Number(n) = v else {
+        return Ok(());
+    };
+
+    let value = match (n.as_i64(), n.as_u64()) {
+        (Some(i), _) => i as i128,
+        (None, Some(u)) => u as i128,
+        (None, None) => return Ok(()),
+    };
+
+    let (min, max) = kind.range();
+    if value < min || value > max {
+        return Err(ProfigError::Validation(format!(
+            "Value '{}' is out of range for {} ({}..={}). Field: '{}'",
+            shown(v, &f.metadata),
+            kind.name(),
+            min,
+            max,
+            &f.name
+        )));
+    }
+
+    Ok(())
+}
+
+/// Human-readable form of a `min`/`max` bound for the field's type.
+pub fn bound_text(v: f64, ty: &FieldType) -> String {
+    match ty {
+        FieldType::Duration => units::format_duration(v),
+        FieldType::Bytes => units::format_bytes(v as u64),
+        _ => v.to_string(),
+    }
+}
+
+/// Text form of a computed default, as `default = "..."` would spell it.
+pub fn default_text<T: serde::Serialize>(value: &T, ty: &FieldType) -> String {
+    match serde_json::to_value(value) {
+        Ok(Value::String(s)) => s,
+        Ok(v) if matches!(ty, FieldType::Duration) => {
+            units::format_duration(units::duration_secs(&v).unwrap_or_default())
+        }
+        Ok(v) => v.to_string(),
+        Err(_) => String::new(),
+    }
+}
+
+/// Parses a duration or byte-size value, checks `min`/`max` in the same units and
+/// returns it in the form the field deserializes from.
+fn check_unit(v: &Value, f: &FieldSchema) -> Result<Value, ProfigError> {
+    let meta = &f.metadata;
+    let raw = match v {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    };
+    let (amount, normalized) = match f.ty {
+        FieldType::Duration => {
+            units::duration_secs(v).map(|secs| (secs, units::duration_value(secs)))
+        }
+        _ => units::byte_count(v).map(|bytes| (bytes as f64, Value::from(bytes))),
+    }
+    .map_err(|reason| {
+        ProfigError::Validation(format!(
+            "Value '{}' is not a valid {}: {}. Field: '{}'",
+            shown(&raw, meta),
+            expected_kind(&f.ty),
+            reason,
+            &f.name
+        ))
+    })?;
+
+    if let Some(min) = meta.min.filter(|min| amount < *min) {
+        return Err(ProfigError::Validation(format!(
+            "Value '{}' less than min ({}). Field: '{}'",
+            shown(&raw, meta),
+            bound_text(min, &f.ty),
+            &f.name
+        )));
+    }
+    if let Some(max) = meta.max.filter(|max| amount > *max) {
+        return Err(ProfigError::Validation(format!(
+            "Value '{}' greater than max ({}). Field: '{}'",
+            shown(&raw, meta),
+            bound_text(max, &f.ty),
+            &f.name
+        )));
+    }
+
+    Ok(normalized)
+}
+
+/// Fails if a field that is neither `Option<T>` nor has a default is missing.
+///
+/// Generated `load` leaves this to serde; it is meant for validating raw values
+/// against an imported schema.
+pub fn check_required(config: &Value, schema: &[FieldSchema]) -> Result<(), Box<dyn Error>> {
+    for f in schema {
+        if f.optional || f.metadata.default.is_some() {
+            continue;
+        }
+
+        if config.get(&f.name).is_none_or(Value::is_null) {
+            return Err(Box::new(ProfigError::Validation(format!(
+                "Missing required field '{}'",
+                &f.name
+            ))));
+        }
+    }
+
+    Ok(())
+}
+
 pub fn validate_fields(config: &mut Value, schema: &[FieldSchema]) -> Result<(), Box<dyn Error>> {
     // for s in schema {
     //     println!("{:#?}", s);
     // }
 
+    let mut problems = vec![];
     for f in schema {
-        let meta = &f.metadata;
-        match config.get(&f.name) {
-            Some(v) if !v.is_null() => {
+        if let Some(problem) = type_mismatch(config.get(&f.name), f) {
+            problems.push(problem);
+            continue;
+        }
+        // Filled-in defaults are checked like any other value, which covers `default_fn`
+        let unset = config.get(&f.name).is_none_or(Value::is_null);
+        let checked = check_field(config, f).and_then(|_| {
+            if unset && config.get(&f.name).is_some_and(|v| !v.is_null()) {
+                check_field(config, f)
+            } else {
+                Ok(())
+            }
+        });
+        if let Err(e) = checked {
+            problems.push(match e.downcast::<ProfigError>() {
+                Ok(e) => match *e {
+                    ProfigError::Validation(msg) => msg,
+                    other => other.to_string(),
+                },
+                Err(e) => e.to_string(),
+            });
+        }
+    }
+
+    // Cross-field rules assume every field is individually valid
+    match problems.len() {
+        0 => check_relations(config, schema),
+        1 => Err(Box::new(ProfigError::Validation(problems.remove(0)))),
+        n => Err(Box::new(ProfigError::Validation(format!(
+            "{} problems found:\n  - {}",
+            n,
+            problems.join("\n  - ")
+        )))),
+    }
+}
+
+/// Name of the kind of value a field expects, for type mismatch messages.
+fn expected_kind(ty: &FieldType) -> &'static str {
+    match ty {
+        FieldType::Int(_) => "integer",
+        FieldType::Float => "number",
+        FieldType::Str => "string",
+        FieldType::Bool => "boolean",
+        FieldType::Duration => "duration",
+        FieldType::Bytes => "byte size",
+        FieldType::Path => "path",
+    }
+}
+
+/// Describes a JSON value for type mismatch messages, e.g. `string "eight"`.
+fn found_kind(v: &Value, meta: &MetaField) -> String {
+    match v {
+        Value::Null => "null".to_string(),
+        Value::Bool(b) => format!("boolean {}", b),
+        Value::Number(n) => format!("number {}", shown(n, meta)),
+        Value::String(s) => format!("string {:?}", shown(s, meta)),
+        Value::Array(_) => "array".to_string(),
+        Value::Object(_) => "table".to_string(),
+    }
+}
+
+/// Reports a value whose JSON type can't be the field's type, such as `"eight"`
+/// for an integer, before any constraint is checked against it.
+fn type_mismatch(value: Option<&Value>, f: &FieldSchema) -> Option<String> {
+    let v = value.filter(|v| !v.is_null())?;
+    let ok = match &f.ty {
+        FieldType::Int(_) => v.is_i64() || v.is_u64(),
+        FieldType::Float => v.is_number(),
+        FieldType::Str | FieldType::Path => v.is_string(),
+        FieldType::Bool => v.is_boolean(),
+        FieldType::Duration => v.is_string() || v.is_number() || v.is_object(),
+        FieldType::Bytes => v.is_string() || v.is_number(),
+    };
+    if ok {
+        return None;
+    }
+
+    Some(format!(
+        "field `{}`: expected {}, found {}",
+        &f.name,
+        expected_kind(&f.ty),
+        found_kind(v, &f.metadata)
+    ))
+}
+
+/// Normalizes one field, checks its constraints and fills in its default.
+fn check_field(config: &mut Value, f: &FieldSchema) -> Result<(), Box<dyn Error>> {
+    let meta = &f.metadata;
+    if let Some(Value::String(s)) = config.get_mut(&f.name) {
+        normalize_str(s, meta);
+    }
+    if matches!(f.ty, FieldType::Duration | FieldType::Bytes) {
+        if let Some(v) = config.get(&f.name).filter(|v| !v.is_null()) {
+            let normalized = check_unit(v, f)?;
+            if let Some(map) = config.as_object_mut() {
+                map.insert(f.name.clone(), normalized);
+            }
+        }
+    }
+
+    match config.get(&f.name) {
+        None | Some(Value::Null) => {
+            if let Some(def) = &meta.default {
                 match &f.ty {
-                    FieldType::Int => {
-                        if let Some(min) = &meta.min {
-                            // println!("Min = {}, Value = {}", min, v);
-                            if less_than_min(v, *min) {
-                                // return Err(Box::new(ProfigError::Validation(format!("Value '{}' less than min. Field: '{}'", v, &f.name))));
-                                return Err(Box::new(ProfigError::Validation(format!(
-                                    "Value '{}' less than min. Field: '{}'",
-                                    v, &f.name
-                                ))));
-                            }
+                    FieldType::Str => {
+                        if let Some(map) = config.as_object_mut() {
+                            map.insert(f.name.clone(), Value::String(def.clone()));
                         }
-
-                        if let Some(max) = &meta.max {
-                            // println!("Max = {}, Value = {}", max, v);
-                            if greater_than_max(v, *max) {
-                                return Err(Box::new(ProfigError::Validation(format!(
-                                    "Value '{}' greater than max. Field: '{}'",
-                                    v, &f.name
-                                ))));
+                    }
+                    // Defaults are checked like configured paths
+                    FieldType::Path => {
+                        check_path(def, f)?;
+                        if let Some(map) = config.as_object_mut() {
+                            map.insert(f.name.clone(), Value::String(def.clone()));
+                        }
+                    }
+                    FieldType::Int(_) => {
+                        if let Ok(parsed) = def.parse() {
+                            if let Some(map) = config.as_object_mut() {
+                                map.insert(f.name.clone(), Value::Number(parsed));
                             }
+                        } else {
+                            return Err(Box::new(ProfigError::Validation(format!(
+                                "Failed to parse default '{}' as integer for field '{}'",
+                                def, &f.name
+                            ))));
                         }
                     }
                     FieldType::Float => {
-                        if let Some(min) = &meta.min {
-                            // println!("Min = {}, Value = {}", min, v);
-                            if less_than_min(v, *min) {
-                                return Err(Box::new(ProfigError::Validation(format!(
-                                    "Value '{}' less than min. Field: '{}'",
-                                    v, &f.name
-                                ))));
+                        if let Ok(parsed) = def.parse() {
+                            if let Some(map) = config.as_object_mut() {
+                                map.insert(f.name.clone(), Value::Number(parsed));
                             }
+                        } else {
+                            return Err(Box::new(ProfigError::Validation(format!(
+                                "Failed to parse default '{}' as float for field '{}'",
+                                def, &f.name
+                            ))));
                         }
-
-                        if let Some(max) = &meta.max {
-                            // println!("Max = {}, Value = {}", max, v);
-                            if greater_than_max(v, *max) {
-                                return Err(Box::new(ProfigError::Validation(format!(
-                                    "Value '{}' greater than max. Field: '{}'",
-                                    v, &f.name
-                                ))));
+                    }
+                    FieldType::Bool => {
+                        if let Ok(parsed) = def.parse::<bool>() {
+                            if let Some(map) = config.as_object_mut() {
+                                map.insert(f.name.clone(), Value::Bool(parsed));
                             }
+                        } else {
+                            return Err(Box::new(ProfigError::Validation(format!(
+                                "Failed to parse default '{}' as bool for field '{}'",
+                                def, &f.name
+                            ))));
                         }
                     }
-                    FieldType::Str => {
-                        if let Some(rx) = &meta.regex {
-                            if !valid_regex(v, rx)? {
-                                return Err(Box::new(ProfigError::Validation(format!(
-                                    "Value '{}' does not match provided regex. Field: '{}'",
-                                    v, &f.name
-                                ))));
-                            }
+                    FieldType::Duration | FieldType::Bytes => {
+                        let normalized = check_unit(&Value::String(def.clone()), f)?;
+                        if let Some(map) = config.as_object_mut() {
+                            map.insert(f.name.clone(), normalized);
                         }
                     }
-                    _ => {}
                 }
             }
+        }
 
-            Some(val) if val.is_null() => {
-                if let Some(def) = &meta.default {
-                    match &f.ty {
-                        FieldType::Str => {
-                            if let Some(map) = config.as_object_mut() {
-                                map.insert(f.name.clone(), Value::String(def.clone()));
-                            }
+        Some(v) => {
+            match &f.ty {
+                FieldType::Int(kind) => {
+                    check_int(v, *kind, f)?;
+
+                    if let Some(min) = &meta.min {
+                        // println!("Min = {}, Value = {}", min, v);
+                        if less_than_min(v, *min) {
+                            // return Err(Box::new(ProfigError::Validation(format!("Value '{}' less than min. Field: '{}'", v, &f.name))));
+                            return Err(Box::new(ProfigError::Validation(format!(
+                                "Value '{}' less than min. Field: '{}'",
+                                shown(v, meta),
+                                &f.name
+                            ))));
                         }
-                        FieldType::Int => {
-                            if let Ok(parsed) = def.parse() {
-                                if let Some(map) = config.as_object_mut() {
-                                    map.insert(f.name.clone(), Value::Number(parsed));
-                                }
-                            } else {
-                                return Err(Box::new(ProfigError::Validation(format!(
-                                    "Failed to parse default '{}' as integer for field '{}'",
-                                    def, &f.name
-                                ))));
-                            }
+                    }
+
+                    if let Some(max) = &meta.max {
+                        // println!("Max = {}, Value = {}", max, v);
+                        if greater_than_max(v, *max) {
+                            return Err(Box::new(ProfigError::Validation(format!(
+                                "Value '{}' greater than max. Field: '{}'",
+                                shown(v, meta),
+                                &f.name
+                            ))));
                         }
-                        FieldType::Float => {
-                            if let Ok(parsed) = def.parse() {
-                                if let Some(map) = config.as_object_mut() {
-                                    map.insert(f.name.clone(), Value::Number(parsed));
-                                }
-                            } else {
-                                return Err(Box::new(ProfigError::Validation(format!(
-                                    "Failed to parse default '{}' as float for field '{}'",
-                                    def, &f.name
-                                ))));
-                            }
+                    }
+
+                    if meta.format.as_deref() == Some("port")
+                        && !v.as_u64().is_some_and(|p| (1..=65535).contains(&p))
+                    {
+                        return Err(Box::new(ProfigError::Validation(format!(
+                            "Value '{}' is not a valid {}. Field: '{}'",
+                            shown(v, meta),
+                            format_description("port"),
+                            &f.name
+                        ))));
+                    }
+                }
+                FieldType::Float => {
+                    if let Some(min) = &meta.min {
+                        // println!("Min = {}, Value = {}", min, v);
+                        if less_than_min(v, *min) {
+                            return Err(Box::new(ProfigError::Validation(format!(
+                                "Value '{}' less than min. Field: '{}'",
+                                shown(v, meta),
+                                &f.name
+                            ))));
                         }
-                        FieldType::Bool => {
-                            if let Ok(parsed) = def.parse::<bool>() {
-                                if let Some(map) = config.as_object_mut() {
-                                    map.insert(f.name.clone(), Value::Bool(parsed));
-                                }
-                            } else {
-                                return Err(Box::new(ProfigError::Validation(format!(
-                                    "Failed to parse default '{}' as bool for field '{}'",
-                                    def, &f.name
-                                ))));
-                            }
+                    }
+
+                    if let Some(max) = &meta.max {
+                        // println!("Max = {}, Value = {}", max, v);
+                        if greater_than_max(v, *max) {
+                            return Err(Box::new(ProfigError::Validation(format!(
+                                "Value '{}' greater than max. Field: '{}'",
+                                shown(v, meta),
+                                &f.name
+                            ))));
                         }
                     }
                 }
-            }
+                FieldType::Path => {
+                    if let Value::String(s) = v {
+                        check_path(s, f)?;
+                    }
+                }
+                FieldType::Str => {
+                    if let Value::String(s) = v {
+                        check_str(s, f)?;
+                    }
 
-            None => {
-                if let Some(def) = &meta.default {
-                    match &f.ty {
-                        FieldType::Str => {
-                            if let Some(map) = config.as_object_mut() {
-                                map.insert(f.name.clone(), Value::String(def.clone()));
-                            }
-                        }
-                        FieldType::Int => {
-                            if let Ok(parsed) = def.parse() {
-                                if let Some(map) = config.as_object_mut() {
-                                    map.insert(f.name.clone(), Value::Number(parsed));
-                                }
-                            } else {
-                                return Err(Box::new(ProfigError::Validation(format!(
-                                    "Failed to parse default '{}' as integer for field '{}'",
-                                    def, &f.name
-                                ))));
-                            }
-                        }
-                        FieldType::Float => {
-                            if let Ok(parsed) = def.parse() {
-                                if let Some(map) = config.as_object_mut() {
-                                    map.insert(f.name.clone(), Value::Number(parsed));
-                                }
-                            } else {
-                                return Err(Box::new(ProfigError::Validation(format!(
-                                    "Failed to parse default '{}' as float for field '{}'",
-                                    def, &f.name
-                                ))));
-                            }
-                        }
-                        FieldType::Bool => {
-                            if let Ok(parsed) = def.parse::<bool>() {
-                                if let Some(map) = config.as_object_mut() {
-                                    map.insert(f.name.clone(), Value::Bool(parsed));
-                                }
-                            } else {
-                                return Err(Box::new(ProfigError::Validation(format!(
-                                    "Failed to parse default '{}' as bool for field '{}'",
-                                    def, &f.name
-                                ))));
-                            }
+                    if let Some(rx) = &meta.regex {
+                        if !valid_regex(v, rx)? {
+                            return Err(Box::new(ProfigError::Validation(format!(
+                                "Value '{}' does not match provided regex. Field: '{}'",
+                                shown(v, meta),
+                                &f.name
+                            ))));
                         }
                     }
                 }
+                _ => {}
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn is_set(value: Option<&Value>) -> bool {
+    !matches!(value, None | Some(Value::Null) | Some(Value::Bool(false)))
+}
+
+fn compare(
+    config: &Value,
+    schema: &[FieldSchema],
+    f: &FieldSchema,
+    other: &str,
+    op: &str,
+) -> Result<(), Box<dyn Error>> {
+    let (Some(a), Some(b)) = (config.get(&f.name), config.get(other)) else {
+        return Ok(());
+    };
+    if a.is_null() || b.is_null() {
+        return Ok(());
+    }
+
+    // Durations are `{ secs, nanos }` objects by now
+    let amount = |v: &Value| match v {
+        Value::Object(_) => units::duration_secs(v).ok(),
+        _ => v.as_f64(),
+    };
+    let (Some(x), Some(y)) = (amount(a), amount(b)) else {
+        return Err(Box::new(ProfigError::Validation(format!(
+            "Cannot compare non-numeric fields '{}' and '{}'",
+            &f.name, other
+        ))));
+    };
+
+    let ok = if op == "greater than" { x > y } else { x < y };
+    if !ok {
+        let other_meta = schema
+            .iter()
+            .find(|g| g.name == other)
+            .map_or(&f.metadata, |g| &g.metadata);
+        return Err(Box::new(ProfigError::Validation(format!(
+            "Value '{}' must be {} '{}' ({}). Field: '{}'",
+            shown(bound_text(x, &f.ty), &f.metadata),
+            op,
+            other,
+            shown(bound_text(y, &f.ty), other_meta),
+            &f.name
+        ))));
+    }
+
+    Ok(())
+}
+
+/// Checks the cross-field rules (`require_if`, `mutually_exclusive`, `gt`, `lt`)
+/// against the whole config, after defaults have been filled in.
+pub fn check_relations(config: &Value, schema: &[FieldSchema]) -> Result<(), Box<dyn Error>> {
+    for f in schema {
+        let meta = &f.metadata;
+
+        for cond in &meta.required_if {
+            let actual = config.get(&cond.field);
+            let applies = match &cond.equals {
+                Some(expected) => actual == Some(expected),
+                None => is_set(actual),
+            };
+
+            if applies && config.get(&f.name).is_none_or(Value::is_null) {
+                let when = match &cond.equals {
+                    Some(expected) => format!("'{}' is {}", cond.field, expected),
+                    None => format!("'{}' is set", cond.field),
+                };
+                return Err(Box::new(ProfigError::Validation(format!(
+                    "Field '{}' is required when {}",
+                    &f.name, when
+                ))));
             }
+        }
+
+        if config.get(&f.name).is_some_and(|v| !v.is_null()) {
+            if let Some(other) = meta
+                .conflicts_with
+                .iter()
+                .find(|o| config.get(o.as_str()).is_some_and(|v| !v.is_null()))
+            {
+                return Err(Box::new(ProfigError::Validation(format!(
+                    "Fields '{}' and '{}' are mutually exclusive; set only one",
+                    &f.name, other
+                ))));
+            }
+        }
 
-            _ => {}
+        if let Some(other) = &meta.gt {
+            compare(config, schema, f, other, "greater than")?;
+        }
+        if let Some(other) = &meta.lt {
+            compare(config, schema, f, other, "less than")?;
         }
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{field, field_with};
+    use serde_json::json;
+
+    fn message(result: Result<(), Box<dyn Error>>) -> String {
+        match *result.unwrap_err().downcast::<ProfigError>().unwrap() {
+            ProfigError::Validation(msg) => msg,
+            other => panic!("expected a validation error, got {}", other),
+        }
+    }
+
+    #[test]
+    fn type_mismatches_name_the_expected_and_found_kinds() {
+        let schema = [field("threads", FieldType::Int(IntKind::U16))];
+        let mut config = json!({ "threads": "eight" });
+
+        assert_eq!(
+            message(validate_fields(&mut config, &schema)),
+            r#"field `threads`: expected integer, found string "eight""#
+        );
+    }
+
+    #[test]
+    fn type_mismatches_redact_secrets() {
+        let secret = MetaField {
+            secret: true,
+            ..MetaField::default()
+        };
+        let schema = [field_with("pin", FieldType::Int(IntKind::U32), secret)];
+        let mut config = json!({ "pin": "1234x" });
+
+        assert_eq!(
+            message(validate_fields(&mut config, &schema)),
+            r#"field `pin`: expected integer, found string "***""#
+        );
+    }
+
+    #[test]
+    fn every_field_problem_is_reported() {
+        let positive = MetaField {
+            min: Some(1.0),
+            ..MetaField::default()
+        };
+        let schema = [
+            field_with("threads", FieldType::Int(IntKind::U16), positive),
+            field("verbose", FieldType::Bool),
+            field("name", FieldType::Str),
+        ];
+        let mut config = json!({ "threads": 0, "verbose": "loud", "name": "app" });
+
+        assert_eq!(
+            message(validate_fields(&mut config, &schema)),
+            "2 problems found:\n  - Value '0' less than min. Field: 'threads'\n  - field `verbose`: expected boolean, found string \"loud\""
+        );
+    }
+
+    #[test]
+    fn values_of_the_right_kind_pass() {
+        let schema = [
+            field("ratio", FieldType::Float),
+            field("timeout", FieldType::Duration),
+        ];
+        let mut config = json!({ "ratio": 1, "timeout": "30s" });
+
+        validate_fields(&mut config, &schema).unwrap();
+    }
+
+    #[test]
+    fn hostnames() {
+        for ok in [
+            "localhost",
+            "example.com",
+            "example.com.",
+            "a-b.c0",
+            "xn--bcher-kva.ch",
+        ] {
+            assert_eq!(check_hostname(ok), Ok(()), "{}", ok);
+        }
+        let long_label = format!("{}.com", "a".repeat(64));
+        for (bad, why) in [
+            ("", "empty hostname"),
+            ("a..b", "empty label"),
+            ("-a.com", "label '-a' starts or ends with '-'"),
+            ("a_b.com", "invalid character '_'"),
+            (long_label.as_str(), "is longer than 63 characters"),
+        ] {
+            let err = check_hostname(bad).unwrap_err();
+            assert!(err.contains(why), "{}: {}", bad, err);
+        }
+    }
+
+    #[test]
+    fn urls() {
+        for ok in [
+            "https://example.com",
+            "http://user:pw@127.0.0.1:8080/path?q=1#frag",
+            "https://[::1]:443/",
+            "http:example.com",
+            "file:///etc/hosts",
+            "postgres://user@/db?host=/sock",
+            "redis://my_cache:6379",
+            "mailto:ops@example.com",
+            "unix:/run/app.sock",
+        ] {
+            assert_eq!(check_url(ok), Ok(()), "{}", ok);
+        }
+        for (bad, why) in [
+            ("example.com", "missing scheme"),
+            ("1http://example.com", "invalid scheme '1http'"),
+            ("https://", "missing host"),
+            ("https://exa mple.com", "contains whitespace"),
+            ("https://bad_host.com", "invalid host 'bad_host.com'"),
+            ("http://[::1", "unterminated IPv6 host"),
+            ("http://example.com:99999", "invalid port '99999'"),
+        ] {
+            let err = check_url(bad).unwrap_err();
+            assert!(err.contains(why), "{}: {}", bad, err);
+        }
+    }
+
+    #[test]
+    fn emails() {
+        for ok in ["ops@example.com", "first.last+tag@mail.example.org"] {
+            assert_eq!(check_email(ok), Ok(()), "{}", ok);
+        }
+        for (bad, why) in [
+            ("example.com", "missing '@'"),
+            ("@example.com", "empty local part"),
+            ("a b@example.com", "invalid character ' ' in local part"),
+            ("ops@localhost", "domain 'localhost' has no '.'"),
+            ("ops@exa_mple.com", "invalid domain"),
+        ] {
+            let err = check_email(bad).unwrap_err();
+            assert!(err.contains(why), "{}: {}", bad, err);
+        }
+    }
+
+    #[test]
+    fn uuids() {
+        assert_eq!(check_uuid("67e55044-10b1-426f-9247-bb680e5fe0c8"), Ok(()));
+        assert_eq!(check_uuid("67E55044-10B1-426F-9247-BB680E5FE0C8"), Ok(()));
+        assert_eq!(
+            check_uuid("67e5504410b1426f9247bb680e5fe0c8"),
+            Err("expected 8-4-4-4-12 hex digits".to_string())
+        );
+        assert_eq!(
+            check_uuid("67e55044-10b1-426f-9247-bb680e5fe0cg"),
+            Err("invalid character 'g'".to_string())
+        );
+    }
+}
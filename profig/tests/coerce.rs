@@ -0,0 +1,39 @@
+use profig::Profig;
+use serde::Deserialize;
+
+#[derive(Profig, Deserialize, Debug)]
+#[profig(format = "json")]
+struct Worker {
+    #[profig(min = 1)]
+    threads: u16,
+
+    #[profig(min_len = 1)]
+    label: String,
+
+    #[profig(default = false)]
+    verbose: bool,
+}
+
+#[test]
+fn lenient_values_are_interpolated_before_coercion() {
+    let value = serde_json::json!({ "threads": "8", "label": "${threads}", "verbose": "yes" });
+
+    let worker = Worker::load_from_value_lenient(value).unwrap();
+
+    assert_eq!(worker.threads, 8);
+    assert_eq!(worker.label, "8");
+    assert!(worker.verbose);
+}
+
+#[test]
+fn strict_loads_report_string_numbers() {
+    let value = serde_json::json!({ "threads": "8", "label": "app" });
+
+    let err = Worker::load_from_value(value).unwrap_err().to_string();
+
+    assert!(
+        err.contains(r#"field `threads`: expected integer, found string "8""#),
+        "{}",
+        err
+    );
+}
@@ -0,0 +1,73 @@
+#![cfg(feature = "toml")]
+
+use profig::Profig;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+#[derive(Profig, Deserialize, Serialize, Debug)]
+#[profig(format = "toml")]
+struct Db {
+    #[profig(secret, min_len = 4)]
+    password: String,
+
+    #[profig(default = "data")]
+    data_dir: PathBuf,
+
+    #[profig(min = 1)]
+    pool: u32,
+}
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("profig-save-{}-{}", name, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn unchanged_references_are_written_back() {
+    std::env::set_var("PROFIG_TEST_SAVE_PASSWORD", "s3cr3t");
+    std::env::set_var("PROFIG_TEST_SAVE_HOME", "/home/app");
+    let dir = temp_dir("refs");
+    let path = dir.join("db.toml").to_string_lossy().into_owned();
+    std::fs::write(
+        &path,
+        "password = \"${PROFIG_TEST_SAVE_PASSWORD}\"\ndata_dir = \"${PROFIG_TEST_SAVE_HOME}/.app\"\npool = 2\n",
+    )
+    .unwrap();
+
+    let mut db = Db::load(&path).unwrap();
+    assert_eq!(db.password, "s3cr3t");
+    db.pool = 4;
+    db.save(&path).unwrap();
+
+    let saved = std::fs::read_to_string(&path).unwrap();
+    assert!(!saved.contains("s3cr3t"), "{}", saved);
+    assert!(
+        saved.contains("password = \"${PROFIG_TEST_SAVE_PASSWORD}\""),
+        "{}",
+        saved
+    );
+    assert!(
+        saved.contains("data_dir = \"${PROFIG_TEST_SAVE_HOME}/.app\""),
+        "{}",
+        saved
+    );
+    assert!(saved.contains("pool = 4"), "{}", saved);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn files_with_includes_are_not_overwritten() {
+    let dir = temp_dir("include");
+    let path = dir.join("db.toml").to_string_lossy().into_owned();
+    std::fs::write(dir.join("base.toml"), "password = \"hunter22\"\n").unwrap();
+    let content = "include = \"base.toml\"\npool = 2\n";
+    std::fs::write(&path, content).unwrap();
+
+    let db = Db::load(&path).unwrap();
+    let err = db.save(&path).unwrap_err().to_string();
+
+    assert!(err.contains("includes other files"), "{}", err);
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), content);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,15 @@
+use profig::Profig;
+use serde::Deserialize;
+
+fn threads() -> u32 {
+    4
+}
+
+#[derive(Profig, Deserialize)]
+#[profig(format = "toml")]
+struct Config {
+    #[profig(default = 4, default_fn = "threads")]
+    threads: u32,
+}
+
+fn main() {}
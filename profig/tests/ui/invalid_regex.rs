@@ -0,0 +1,11 @@
+use profig::Profig;
+use serde::Deserialize;
+
+#[derive(Profig, Deserialize)]
+#[profig(format = "toml")]
+struct Config {
+    #[profig(regex = "^[a-z+$")]
+    name: String,
+}
+
+fn main() {}
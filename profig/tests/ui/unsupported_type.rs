@@ -0,0 +1,12 @@
+use profig::Profig;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+#[derive(Profig, Deserialize)]
+#[profig(format = "toml")]
+struct Config {
+    #[profig(non_empty)]
+    labels: HashMap<String, String>,
+}
+
+fn main() {}